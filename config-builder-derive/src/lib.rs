@@ -18,8 +18,26 @@
 //!        self.field2 = value;
 //!        self
 //!    }
-//!    pub fn new() -> Self {
-//!        Self::default()
+//!    pub const FIELD_NAMES: &[&str] = &["field1", "field2"];
+//!    pub fn field(&self, name: &str) -> Option<&Option<String>> {
+//!        match name {
+//!            "field1" => Some(&self.field1),
+//!            "field2" => Some(&self.field2),
+//!            _ => None,
+//!        }
+//!    }
+//!    pub fn field_mut(&mut self, name: &str) -> Option<&mut Option<String>> {
+//!        match name {
+//!            "field1" => Some(&mut self.field1),
+//!            "field2" => Some(&mut self.field2),
+//!            _ => None,
+//!        }
+//!    }
+//!    pub fn merge(self, other: Self) -> Self {
+//!        Self {
+//!            field1: other.field1.or(self.field1),
+//!            field2: other.field2.or(self.field2),
+//!        }
 //!    }
 //!}
 use proc_macro::{self, TokenStream};
@@ -57,6 +75,8 @@ pub fn derive(input: TokenStream) -> TokenStream {
         .map(|field| field.ident.clone().unwrap())
         .collect();
 
+    let names: Vec<String> = idents.iter().map(ToString::to_string).collect();
+
     let output = quote! {
         impl #struct_name {
             #(
@@ -65,6 +85,29 @@ pub fn derive(input: TokenStream) -> TokenStream {
                     self
                 }
             )*
+            /// The name of every field, in declaration order.
+            pub const FIELD_NAMES: &[&str] = &[ #(#names,)* ];
+            /// Look up a field by name.
+            pub fn field(&self, name: &str) -> Option<&Option<String>> {
+                match name {
+                    #(#names => Some(&self.#idents),)*
+                    _ => None,
+                }
+            }
+            /// Look up a field by name, for modification.
+            pub fn field_mut(&mut self, name: &str) -> Option<&mut Option<String>> {
+                match name {
+                    #(#names => Some(&mut self.#idents),)*
+                    _ => None,
+                }
+            }
+            /// Combine two builders, preferring the values that are set in `other`.
+            #[must_use]
+            pub fn merge(self, other: Self) -> Self {
+                Self {
+                    #(#idents: other.#idents.or(self.#idents),)*
+                }
+            }
         }
     };
     output.into()
@@ -1,11 +1,20 @@
 //! Configuration and validation.
 use config_builder_derive::ConfigBuilder;
 
+use crate::consts::WORD_COUNT_MAX;
 use crate::consts::default;
+use crate::entropy::Entropy;
+use crate::policy::Policy;
+use crate::template::Template;
+use crate::types::CharacterClass;
+use crate::types::GroupPlacement;
 use crate::types::Integer;
+use crate::types::PaddingMode;
 use crate::types::PaddingType;
 use crate::types::RngType;
+use crate::types::SeparatorType;
 use crate::types::StrEnum;
+use crate::types::UppercaseLimitType;
 use crate::types::ValidationError;
 use crate::types::WordTransformationType;
 
@@ -19,22 +28,53 @@ pub struct Config {
     pub word_min_length: u8,
     /// maximum length of a chosen word
     pub word_max_length: u8,
-    /// transformation to apply to the selected words
-    pub word_transformation: WordTransformationType,
+    /// transformations to apply to the selected words, in order
+    pub word_transformation: Vec<WordTransformationType>,
+    /// characters and their substitutes for [`WordTransformationType::Leet`] and [`WordTransformationType::RandomLeet`]
+    pub leet_table: Vec<(char, char)>,
+    /// number of letters [`WordTransformationType::RandomCase`] uppercases, or 0 to flip a coin for each
+    pub uppercase_limit: u8,
+    /// whether the uppercase_limit applies to each word or to the whole password
+    pub uppercase_limit_type: UppercaseLimitType,
     /// number of digits to prepend
     pub digits_before: u8,
     /// number of digits to append
     pub digits_after: u8,
+    /// where to place groups of digits and symbols between words
+    pub group_placement: GroupPlacement,
+    /// number of groups for [`GroupPlacement::RandomGaps`]
+    pub group_count: u8,
+    /// number of digits in each group
+    pub group_digits: u8,
+    /// number of symbols in each group
+    pub group_symbols: u8,
+    /// list of characters from which to choose the group symbols
+    pub symbol_characters: Vec<char>,
     /// how to apply padding
     pub padding_type: PaddingType,
     /// how much to pad
     pub padding_length: u8,
     /// list of characters from which to choose the padding character
     pub padding_characters: Vec<char>,
+    /// how to choose the padding characters for [`PaddingType::Fixed`] and [`PaddingType::Adaptive`]
+    pub padding_mode: PaddingMode,
     /// list of characters from which to choose the separator character
     pub separator_characters: Vec<char>,
+    /// how to choose the separators
+    pub separator_type: SeparatorType,
+    /// list of tokens from which to choose the separators for [`SeparatorType::Tokens`]
+    pub separator_tokens: Vec<String>,
     /// method of random number generation
     pub rng_type: RngType,
+    /// if not 0, choose the fewest words that give at least this many bits of entropy
+    pub min_entropy: u8,
+    /// rules that every password must follow
+    pub policy: Policy,
+    /// if set, the shape of every password, which replaces the word count, digits, groups and padding
+    ///
+    /// The [`Config::word_transformation`] chain is still applied to each word,
+    /// before the word's token sets its case.
+    pub template: Option<Template>,
 }
 
 impl Default for Config {
@@ -51,13 +91,30 @@ pub struct ConfigBuilder {
     word_min_length: Option<String>,
     word_max_length: Option<String>,
     word_transformation: Option<String>,
+    leet_table: Option<String>,
+    uppercase_limit: Option<String>,
+    uppercase_limit_type: Option<String>,
     digits_before: Option<String>,
     digits_after: Option<String>,
+    group_placement: Option<String>,
+    group_count: Option<String>,
+    group_digits: Option<String>,
+    group_symbols: Option<String>,
+    symbol_characters: Option<String>,
     padding_type: Option<String>,
     padding_length: Option<String>,
     padding_characters: Option<String>,
+    padding_mode: Option<String>,
     separator_characters: Option<String>,
+    separator_type: Option<String>,
+    separator_tokens: Option<String>,
     rng_type: Option<String>,
+    min_entropy: Option<String>,
+    max_length: Option<String>,
+    required_characters: Option<String>,
+    max_repeated: Option<String>,
+    banned_characters: Option<String>,
+    template: Option<String>,
 }
 
 /// Ensure an [`Integer`] is between `min` and `max`.
@@ -112,15 +169,145 @@ fn uniquify_chars(value: Option<String>, default: &[char]) -> Vec<char> {
     )
 }
 
+/// Turn a comma separated list of tokens into tokens with no duplicates.
+/// Tokens are not trimmed, so that they may start or end with spaces, and empty tokens are skipped.
+/// If no `value` is provided, return `default`
+fn uniquify_tokens(value: Option<String>, default: &[&str]) -> Vec<String> {
+    value.map_or_else(
+        || default.iter().map(|token| (*token).to_owned()).collect(),
+        |inner| {
+            let mut result: Vec<String> = inner
+                .split(',')
+                .filter(|token| !token.is_empty())
+                .map(str::to_owned)
+                .collect();
+            result.sort_unstable();
+            result.dedup();
+            result
+        },
+    )
+}
+
+/// Turn a comma separated list of [`StrEnum`] member names into members with no duplicates.
+/// If no `value` is provided, return an empty list.
+fn validate_enum_list<T: StrEnum + PartialEq>(
+    value: Option<String>,
+) -> Result<Vec<T>, ValidationError> {
+    let mut result = Vec::new();
+    for name in value
+        .iter()
+        .flat_map(|inner| inner.split(','))
+        .map(str::trim)
+    {
+        if name.is_empty() {
+            continue;
+        }
+        let member = *T::to_member(&name.to_ascii_lowercase())?;
+        if !result.contains(&member) {
+            result.push(member);
+        }
+    }
+    Ok(result)
+}
+
+/// Turn a comma separated list of transformations into the chain they are applied in,
+/// leaving out `none`, so that `none` on its own is an empty chain.
+/// If no `value` is provided, return a chain of the default transformation.
+fn validate_transformations(
+    value: Option<String>,
+) -> Result<Vec<WordTransformationType>, ValidationError> {
+    let Some(inner) = value else {
+        return Ok(vec![WordTransformationType::default()]);
+    };
+    let mut result = Vec::new();
+    for name in inner
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let member = *WordTransformationType::to_member(&name.to_ascii_lowercase())?;
+        if member != WordTransformationType::None {
+            result.push(member);
+        }
+    }
+    Ok(result)
+}
+
+/// Turn a comma separated list of characters, each followed by its substitute, into a leet table.
+/// If no `value` is provided, return the default table.
+pub fn validate_leet_table(value: Option<String>) -> Result<Vec<(char, char)>, ValidationError> {
+    let Some(inner) = value else {
+        return Ok(default::LEET_TABLE.to_vec());
+    };
+    let mut result: Vec<(char, char)> = Vec::new();
+    for entry in inner
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let mut chars = entry.chars();
+        let (Some(from), Some(to), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(ValidationError::Message(format!(
+                "`{entry}` in the leet table must be a character followed by its substitute"
+            )));
+        };
+        if result
+            .iter()
+            .any(|(seen, _)| seen.to_lowercase().eq(from.to_lowercase()))
+        {
+            return Err(ValidationError::Message(format!(
+                "the leet table substitutes `{from}` more than once"
+            )));
+        }
+        result.push((from, to));
+    }
+    Ok(result)
+}
+
+/// Remove the `items` that are `banned`.
+/// It is an error to remove every item, as that would silently change the password's shape.
+fn remove_banned<T>(
+    mut items: Vec<T>,
+    banned: impl Fn(&T) -> bool,
+    name: &str,
+) -> Result<Vec<T>, ValidationError> {
+    let had_items = !items.is_empty();
+    items.retain(|item| !banned(item));
+    if had_items && items.is_empty() {
+        return Err(ValidationError::Message(format!(
+            "every {name} is banned by the password policy"
+        )));
+    }
+    Ok(items)
+}
+
 /// Setters are auto generated by [`strenum_derive::StrEnum`].
 impl ConfigBuilder {
+    /// The settings that hold an integer, which are written as numbers rather than strings.
+    pub const INTEGER_FIELDS: [&'static str; 14] = [
+        "count",
+        "word_count",
+        "word_min_length",
+        "word_max_length",
+        "uppercase_limit",
+        "digits_before",
+        "digits_after",
+        "group_count",
+        "group_digits",
+        "group_symbols",
+        "padding_length",
+        "min_entropy",
+        "max_length",
+        "max_repeated",
+    ];
     pub fn new() -> Self {
         Self::default()
     }
     pub fn build(self) -> Result<Config, ValidationError> {
         // TODO add constraints to consts.rs
         let count = validate_int::<u8>(self.count, 1, 255, default::COUNT)?;
-        let word_count = validate_int::<u8>(self.word_count, 0, 32, default::WORD_COUNT)?;
+        let word_count =
+            validate_int::<u8>(self.word_count, 0, WORD_COUNT_MAX, default::WORD_COUNT)?;
         let word_min_length =
             validate_int::<u8>(self.word_min_length, 1, 255, default::WORD_MIN_LENGTH)?;
         let word_max_length = validate_int::<u8>(
@@ -129,11 +316,35 @@ impl ConfigBuilder {
             255,
             default::WORD_MAX_LENGTH,
         )?;
-        let word_transformation =
-            validate_enum::<WordTransformationType>(self.word_transformation)?;
+        let word_transformation = validate_transformations(self.word_transformation)?;
+        let uppercase_limit =
+            validate_int::<u8>(self.uppercase_limit, 0, 255, default::UPPERCASE_LIMIT)?;
+        let uppercase_limit_type = validate_enum::<UppercaseLimitType>(self.uppercase_limit_type)?;
         let digits_before = validate_int::<u8>(self.digits_before, 0, 255, default::DIGITS_BEFORE)?;
         let digits_after = validate_int::<u8>(self.digits_after, 0, 255, default::DIGITS_AFTER)?;
-        let padding_characters = uniquify_chars(self.padding_characters, &default::SYMBOL_ALPHABET);
+        let banned_characters = uniquify_chars(self.banned_characters, &[]);
+        let leet_table = remove_banned(
+            validate_leet_table(self.leet_table)?,
+            |(_, to)| banned_characters.contains(to),
+            "leet substitution",
+        )?;
+        let group_placement = validate_enum::<GroupPlacement>(self.group_placement)?;
+        let group_count =
+            validate_int::<u8>(self.group_count, 1, WORD_COUNT_MAX, default::GROUP_COUNT)?;
+        let group_digits = validate_int::<u8>(self.group_digits, 0, 255, default::GROUP_DIGITS)?;
+        let group_symbols = validate_int::<u8>(self.group_symbols, 0, 255, default::GROUP_SYMBOLS)?;
+        let mut symbol_characters = remove_banned(
+            uniquify_chars(self.symbol_characters, &default::SYMBOL_ALPHABET),
+            |c| banned_characters.contains(c),
+            "symbol character",
+        )?;
+        // sorted even by default, so that a config converted back into a builder builds the same
+        symbol_characters.sort_unstable();
+        let padding_characters = remove_banned(
+            uniquify_chars(self.padding_characters, &default::SYMBOL_ALPHABET),
+            |c| banned_characters.contains(c),
+            "padding character",
+        )?;
         let padding_type = if padding_characters.is_empty() {
             PaddingType::None
         } else {
@@ -143,27 +354,333 @@ impl ConfigBuilder {
             match padding_type {
                 PaddingType::Fixed => default::PADDING_LENGTH_FIXED,
                 PaddingType::Adaptive => default::PADDING_LENGTH_ADAPTIVE,
+                PaddingType::Symmetric => default::PADDING_LENGTH_SYMMETRIC,
                 PaddingType::None => 0,
             }
         })?;
-        let separator_characters =
-            uniquify_chars(self.separator_characters, &default::SYMBOL_ALPHABET);
+        if padding_type == PaddingType::Symmetric
+            && padding_length as usize > padding_characters.len()
+        {
+            return Err(ValidationError::Message(format!(
+                "symmetric padding of length {padding_length} needs at least {padding_length} padding characters to choose from, only {} were given",
+                padding_characters.len()
+            )));
+        }
+        let padding_mode = validate_enum::<PaddingMode>(self.padding_mode)?;
+        let separator_characters = remove_banned(
+            uniquify_chars(self.separator_characters, &default::SYMBOL_ALPHABET),
+            |c| banned_characters.contains(c),
+            "separator character",
+        )?;
+        let separator_type = validate_enum::<SeparatorType>(self.separator_type)?;
+        let separator_tokens = remove_banned(
+            uniquify_tokens(self.separator_tokens, &default::SEPARATOR_TOKENS),
+            |token| token.contains(banned_characters.as_slice()),
+            "separator token",
+        )?;
         let rng_type = validate_enum::<RngType>(self.rng_type)?;
+        let min_entropy = validate_int::<u8>(self.min_entropy, 0, 255, default::MIN_ENTROPY)?;
+        let policy = Policy {
+            max_length: validate_int::<u8>(self.max_length, 0, 255, default::MAX_LENGTH)?,
+            required_characters: validate_enum_list::<CharacterClass>(self.required_characters)?,
+            max_repeated: validate_int::<u8>(self.max_repeated, 0, 255, default::MAX_REPEATED)?,
+            banned_characters,
+        };
 
-        Ok(Config {
+        let template = self
+            .template
+            .filter(|template| !template.is_empty())
+            .map(|template| Template::parse(&template))
+            .transpose()?;
+
+        let config = Config {
             count,
             word_count,
             word_min_length,
             word_max_length,
             word_transformation,
+            leet_table,
+            uppercase_limit,
+            uppercase_limit_type,
             digits_before,
             digits_after,
+            group_placement,
+            group_count,
+            group_digits,
+            group_symbols,
+            symbol_characters,
             padding_type,
             padding_length,
             padding_characters,
+            padding_mode,
             separator_characters,
+            separator_type,
+            separator_tokens,
             rng_type,
-        })
+            min_entropy,
+            policy,
+            template,
+        };
+        if config.template.is_none() {
+            config.validate_groups()?;
+            // the word count is not final until the minimum entropy is resolved,
+            // which skips word counts that cannot fit, see `Config::resolve_min_entropy`
+            if config.min_entropy == 0 {
+                config.word_length_budgets()?;
+            }
+        }
+        Ok(config)
+    }
+    /// Validate the setting `name` on its own, without the rules that depend on other settings.
+    ///
+    /// Settings that come from several places are checked with this as they are read,
+    /// and then together by [`ConfigBuilder::build`] once every source has been merged.
+    pub fn validate_field(name: &str, value: &str) -> Result<(), ValidationError> {
+        let value = Some(value.to_owned());
+        match name {
+            "count" | "word_min_length" | "word_max_length" => {
+                validate_int::<u8>(value, 1, 255, 1).map(drop)
+            }
+            "word_count" => validate_int::<u8>(value, 0, WORD_COUNT_MAX, 0).map(drop),
+            "group_count" => validate_int::<u8>(value, 1, WORD_COUNT_MAX, 1).map(drop),
+            "uppercase_limit" | "digits_before" | "digits_after" | "group_digits"
+            | "group_symbols" | "padding_length" | "min_entropy" | "max_length"
+            | "max_repeated" => validate_int::<u8>(value, 0, 255, 0).map(drop),
+            "word_transformation" => validate_transformations(value).map(drop),
+            "leet_table" => validate_leet_table(value).map(drop),
+            "uppercase_limit_type" => validate_enum::<UppercaseLimitType>(value).map(drop),
+            "group_placement" => validate_enum::<GroupPlacement>(value).map(drop),
+            "padding_type" => validate_enum::<PaddingType>(value).map(drop),
+            "padding_mode" => validate_enum::<PaddingMode>(value).map(drop),
+            "separator_type" => validate_enum::<SeparatorType>(value).map(drop),
+            "rng_type" => validate_enum::<RngType>(value).map(drop),
+            "required_characters" => validate_enum_list::<CharacterClass>(value).map(drop),
+            "template" => value
+                .filter(|template| !template.is_empty())
+                .map_or(Ok(()), |template| Template::parse(&template).map(drop)),
+            name if Self::FIELD_NAMES.contains(&name) => Ok(()),
+            name => Err(ValidationError::UnknownKey(
+                name.to_owned(),
+                Self::FIELD_NAMES,
+            )),
+        }
+    }
+}
+
+/// Convert a [`Config`] back into values that would build it.
+impl From<&Config> for ConfigBuilder {
+    fn from(config: &Config) -> Self {
+        Self::new()
+            .count(Some(config.count.to_string()))
+            .word_count(Some(config.word_count.to_string()))
+            .word_min_length(Some(config.word_min_length.to_string()))
+            .word_max_length(Some(config.word_max_length.to_string()))
+            .word_transformation(Some(if config.word_transformation.is_empty() {
+                WordTransformationType::None.to_string()
+            } else {
+                config
+                    .word_transformation
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(",")
+            }))
+            .leet_table(Some(
+                config
+                    .leet_table
+                    .iter()
+                    .map(|(from, to)| format!("{from}{to}"))
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ))
+            .uppercase_limit(Some(config.uppercase_limit.to_string()))
+            .uppercase_limit_type(Some(config.uppercase_limit_type.to_string()))
+            .digits_before(Some(config.digits_before.to_string()))
+            .digits_after(Some(config.digits_after.to_string()))
+            .group_placement(Some(config.group_placement.to_string()))
+            .group_count(Some(config.group_count.to_string()))
+            .group_digits(Some(config.group_digits.to_string()))
+            .group_symbols(Some(config.group_symbols.to_string()))
+            .symbol_characters(Some(config.symbol_characters.iter().collect()))
+            .padding_type(Some(config.padding_type.to_string()))
+            .padding_length(Some(config.padding_length.to_string()))
+            .padding_characters(Some(config.padding_characters.iter().collect()))
+            .padding_mode(Some(config.padding_mode.to_string()))
+            .separator_characters(Some(config.separator_characters.iter().collect()))
+            .separator_type(Some(config.separator_type.to_string()))
+            .separator_tokens(Some(config.separator_tokens.join(",")))
+            .rng_type(Some(config.rng_type.to_string()))
+            .min_entropy(Some(config.min_entropy.to_string()))
+            .max_length(Some(config.policy.max_length.to_string()))
+            .required_characters(Some(
+                config
+                    .policy
+                    .required_characters
+                    .iter()
+                    .map(StrEnum::to_static_str)
+                    .collect::<Vec<&str>>()
+                    .join(","),
+            ))
+            .max_repeated(Some(config.policy.max_repeated.to_string()))
+            .banned_characters(Some(config.policy.banned_characters.iter().collect()))
+            .template(Some(
+                config
+                    .template
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            ))
+    }
+}
+
+impl Config {
+    /// How many characters each group between words has.
+    pub fn group_length(&self) -> usize {
+        let symbols = if self.symbol_characters.is_empty() {
+            0
+        } else {
+            self.group_symbols as usize
+        };
+        self.group_digits as usize + symbols
+    }
+    /// How many groups of digits and symbols are placed between words.
+    ///
+    /// [`GroupPlacement::RandomGaps`] can place no more groups than there are gaps.
+    pub fn group_total(&self) -> usize {
+        if self.group_length() == 0 {
+            return 0;
+        }
+        let gaps = (self.word_count as usize).saturating_sub(1);
+        match self.group_placement {
+            GroupPlacement::None => 0,
+            GroupPlacement::EveryGap => gaps,
+            GroupPlacement::RandomGaps => gaps.min(self.group_count as usize),
+        }
+    }
+    /// It is an error for groups to be empty, or to ask for more groups than there are gaps between words.
+    fn validate_groups(&self) -> Result<(), ValidationError> {
+        if self.group_placement == GroupPlacement::None {
+            return Ok(());
+        }
+        if self.group_length() == 0 {
+            return Err(ValidationError::Message(
+                "groups between words need at least one digit or symbol".to_owned(),
+            ));
+        }
+        let gaps = (self.word_count as usize).saturating_sub(1);
+        if self.group_placement == GroupPlacement::RandomGaps && self.group_count as usize > gaps {
+            return Err(ValidationError::Message(format!(
+                "a group_count of {} is more than the {gaps} gaps between {} words",
+                self.group_count, self.word_count
+            )));
+        }
+        Ok(())
+    }
+    /// How many separators each gap between words and pseudo-words is chosen from.
+    pub fn separator_choices(&self) -> usize {
+        match self.separator_type {
+            SeparatorType::Single | SeparatorType::PerGap => self.separator_characters.len(),
+            SeparatorType::Tokens => self.separator_tokens.len(),
+        }
+    }
+    /// The most characters a single separator may have.
+    pub fn separator_max_length(&self) -> usize {
+        match self.separator_type {
+            SeparatorType::Single | SeparatorType::PerGap => {
+                usize::from(!self.separator_characters.is_empty())
+            }
+            SeparatorType::Tokens => self
+                .separator_tokens
+                .iter()
+                .map(|token| token.chars().count())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+    /// The most characters each word may have, so that every password fits in the policy's `max_length`.
+    ///
+    /// The room left after digits, groups, the longest possible separators and fixed padding is shared out as evenly as possible,
+    /// with earlier words getting any remainder, and no word may be longer than `word_max_length`.
+    /// Without a `max_length`, every word may be `word_max_length` characters long.
+    ///
+    /// It is an error if the words cannot fit, or adaptive padding would always go past `max_length`.
+    pub fn word_length_budgets(&self) -> Result<Vec<usize>, ValidationError> {
+        let words = self.word_count as usize;
+        let word_max_length = self.word_max_length as usize;
+        let max_length = self.policy.max_length as usize;
+        if max_length == 0 {
+            return Ok(vec![word_max_length; words]);
+        }
+        if self.padding_type == PaddingType::Adaptive && self.padding_length as usize > max_length {
+            return Err(ValidationError::Message(format!(
+                "a padding_length of {} is longer than the max_length of {max_length}",
+                self.padding_length
+            )));
+        }
+        let groups = self.group_total();
+        let digits =
+            self.digits_before as usize + self.digits_after as usize + groups * self.group_length();
+        let parts = words
+            + groups
+            + usize::from(self.digits_before > 0)
+            + usize::from(self.digits_after > 0);
+        let separators = parts.saturating_sub(1) * self.separator_max_length();
+        let padding = match self.padding_type {
+            PaddingType::Fixed | PaddingType::Symmetric => 2 * self.padding_length as usize,
+            PaddingType::None | PaddingType::Adaptive => 0,
+        };
+        let needed = digits + separators + padding + words * self.word_min_length as usize;
+        if needed > max_length {
+            return Err(ValidationError::Message(format!(
+                "a max_length of {max_length} is too short, passwords made with these settings have at least {needed} characters"
+            )));
+        }
+        let room = max_length - digits - separators - padding;
+        Ok((0..words)
+            .map(|i| (room / words + usize::from(i < room % words)).min(word_max_length))
+            .collect())
+    }
+    /// If a minimum entropy is configured, replace `word_count` with the fewest words that reach it.
+    ///
+    /// `wordlist_len` is the number of words left after filtering by length,
+    /// see [`crate::password_maker::PasswordMaker::filter_wordlist`].
+    /// Word counts whose words cannot fit in the policy's `max_length`, see [`Config::word_length_budgets`],
+    /// or that leave too few gaps for the groups are skipped.
+    /// Without a template, at least one word is chosen even if digits and padding alone reach the target.
+    /// Every other setting is left as is.
+    pub fn resolve_min_entropy(&mut self, wordlist_len: usize) -> Result<(), ValidationError> {
+        self.resolve_min_entropy_with(|config| Entropy::estimate(config, wordlist_len).total())
+    }
+    /// Like [`Config::resolve_min_entropy`], but with a custom way of working out
+    /// the entropy of each candidate config.
+    pub(crate) fn resolve_min_entropy_with(
+        &mut self,
+        entropy: impl Fn(&Self) -> f64,
+    ) -> Result<(), ValidationError> {
+        if self.min_entropy == 0 {
+            return Ok(());
+        }
+        let target = f64::from(self.min_entropy);
+        let mut candidate = self.clone();
+        let mut max = 0.0_f64;
+        // without a template, at least one word is always chosen
+        let min_word_count = u8::from(self.template.is_none());
+        for word_count in min_word_count..=WORD_COUNT_MAX {
+            candidate.word_count = word_count;
+            if candidate.template.is_none()
+                && (candidate.validate_groups().is_err()
+                    || candidate.word_length_budgets().is_err())
+            {
+                continue;
+            }
+            let bits = entropy(&candidate);
+            if bits >= target {
+                self.word_count = word_count;
+                return Ok(());
+            }
+            max = max.max(bits);
+        }
+        Err(ValidationError::UnreachableEntropy(self.min_entropy, max))
     }
 }
 
@@ -180,8 +697,8 @@ mod tests {
         assert_eq!(config.word_min_length, default::WORD_MIN_LENGTH);
         assert_eq!(config.word_max_length, default::WORD_MAX_LENGTH);
         assert_eq!(
-            discriminant(&config.word_transformation),
-            discriminant(&WordTransformationType::default())
+            config.word_transformation,
+            [WordTransformationType::default()]
         );
         assert_eq!(config.digits_before, default::DIGITS_BEFORE);
         assert_eq!(config.digits_after, default::DIGITS_AFTER);
@@ -213,6 +730,392 @@ mod tests {
         assert!(what);
     }
 
+    #[test]
+    fn test_config_builder_from_config() {
+        let config = ConfigBuilder::new()
+            .count(Some("3".to_owned()))
+            .word_count(Some("7".to_owned()))
+            .word_min_length(Some("5".to_owned()))
+            .word_max_length(Some("6".to_owned()))
+            .word_transformation(Some("capitalize-first, leet,reverse".to_owned()))
+            .leet_table(Some("a4, E3,o0".to_owned()))
+            .uppercase_limit(Some("2".to_owned()))
+            .uppercase_limit_type(Some("password".to_owned()))
+            .digits_before(Some("1".to_owned()))
+            .digits_after(Some("4".to_owned()))
+            .group_placement(Some("random-gaps".to_owned()))
+            .group_count(Some("2".to_owned()))
+            .group_digits(Some("2".to_owned()))
+            .group_symbols(Some("1".to_owned()))
+            .symbol_characters(Some("#%".to_owned()))
+            .padding_type(Some("adaptive".to_owned()))
+            .padding_length(Some("30".to_owned()))
+            .padding_characters(Some("#\"".to_owned()))
+            .padding_mode(Some("per-character".to_owned()))
+            .separator_characters(Some(" ".to_owned()))
+            .separator_type(Some("Tokens".to_owned()))
+            .separator_tokens(Some("::,-".to_owned()))
+            .rng_type(Some("csprng".to_owned()))
+            .min_entropy(Some("70".to_owned()))
+            .max_length(Some("80".to_owned()))
+            .required_characters(Some("Upper, digit,upper".to_owned()))
+            .max_repeated(Some("2".to_owned()))
+            .banned_characters(Some("l1\"".to_owned()))
+            .template(Some("{Word}{sep}{dd}".to_owned()))
+            .build()
+            .unwrap();
+        let builder = ConfigBuilder::from(&config);
+        for name in ConfigBuilder::FIELD_NAMES {
+            assert!(builder.field(name).unwrap().is_some(), "{name}");
+        }
+        assert_eq!(config, builder.build().unwrap());
+    }
+
+    #[test]
+    fn test_resolve_min_entropy_disabled() {
+        let mut config = ConfigBuilder::new().build().unwrap();
+        config.resolve_min_entropy(7776).unwrap();
+        assert_eq!(config.word_count, default::WORD_COUNT);
+    }
+
+    #[test]
+    fn test_resolve_min_entropy_picks_fewest_words() {
+        let mut config = ConfigBuilder::new()
+            .word_count(Some("10".to_owned()))
+            .min_entropy(Some("80".to_owned()))
+            .build()
+            .unwrap();
+        config.resolve_min_entropy(7776).unwrap();
+        assert_eq!(config.word_count, 6);
+        assert!(Entropy::estimate(&config, 7776).total() >= 80.0);
+        config.word_count = 5;
+        assert!(Entropy::estimate(&config, 7776).total() < 80.0);
+    }
+
+    #[test]
+    fn test_resolve_min_entropy_small_target() {
+        // digits and padding alone reach 1 bit, but a word is still chosen
+        for min_entropy in ["1", "10"] {
+            let mut config = ConfigBuilder::new()
+                .min_entropy(Some(min_entropy.to_owned()))
+                .build()
+                .unwrap();
+            config.resolve_min_entropy(7776).unwrap();
+            assert_eq!(config.word_count, 1);
+        }
+    }
+
+    #[test]
+    fn test_resolve_min_entropy_unreachable() {
+        let mut config = ConfigBuilder::new()
+            .min_entropy(Some("255".to_owned()))
+            .build()
+            .unwrap();
+        let err = config.resolve_min_entropy(2).unwrap_err();
+        assert!(matches!(err, ValidationError::UnreachableEntropy(255, _)));
+        assert_eq!(config.word_count, default::WORD_COUNT);
+    }
+
+    #[test]
+    fn test_resolve_min_entropy_max_length() {
+        // 4 words do not fit in 16 characters, but 2 do
+        let mut config = ConfigBuilder::new()
+            .max_length(Some("16".to_owned()))
+            .min_entropy(Some("20".to_owned()))
+            .build()
+            .unwrap();
+        config.resolve_min_entropy(7776).unwrap();
+        assert_eq!(config.word_count, 1);
+        assert!(config.word_length_budgets().is_ok());
+
+        // no word count both fits and reaches 80 bits
+        config.min_entropy = 80;
+        let err = config.resolve_min_entropy(7776).unwrap_err();
+        assert!(matches!(err, ValidationError::UnreachableEntropy(80, _)));
+    }
+
+    #[test]
+    fn test_resolve_min_entropy_leaves_room_for_groups() {
+        let mut config = ConfigBuilder::new()
+            .word_count(Some("6".to_owned()))
+            .group_placement(Some("random-gaps".to_owned()))
+            .group_count(Some("3".to_owned()))
+            .min_entropy(Some("1".to_owned()))
+            .build()
+            .unwrap();
+        config.resolve_min_entropy(7776).unwrap();
+        assert_eq!(config.word_count, 4);
+    }
+
+    #[test]
+    fn test_required_characters() {
+        let config = ConfigBuilder::new()
+            .required_characters(Some("upper,DIGIT,,upper".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.policy.required_characters,
+            [CharacterClass::Upper, CharacterClass::Digit]
+        );
+        ConfigBuilder::new()
+            .required_characters(Some("upper,emoji".to_owned()))
+            .build()
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_banned_characters_are_removed() {
+        let config = ConfigBuilder::new()
+            .padding_characters(Some("!?".to_owned()))
+            .separator_characters(Some("-?".to_owned()))
+            .banned_characters(Some("?".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.padding_characters, ['!']);
+        assert_eq!(config.separator_characters, ['-']);
+        let err = ConfigBuilder::new()
+            .separator_characters(Some("-".to_owned()))
+            .banned_characters(Some("-".to_owned()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "every separator character is banned by the password policy"
+        );
+        // choosing no separators at all is still fine
+        ConfigBuilder::new()
+            .separator_characters(Some(String::new()))
+            .banned_characters(Some("-".to_owned()))
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_word_length_budgets() {
+        let mut config = ConfigBuilder::new().build().unwrap();
+        assert_eq!(config.word_length_budgets().unwrap(), [11, 11, 11, 11]);
+        // 2 digits, 4 separators and 2 padding characters leave 14 for 4 words
+        config.policy.max_length = 22;
+        assert_eq!(config.word_length_budgets().unwrap(), [4, 4, 3, 3]);
+        config.policy.max_length = 100;
+        assert_eq!(config.word_length_budgets().unwrap(), [11, 11, 11, 11]);
+        config.policy.max_length = 19;
+        config.word_length_budgets().unwrap_err();
+        config.separator_characters = Vec::new();
+        assert_eq!(config.word_length_budgets().unwrap(), [4, 4, 4, 3]);
+        // the longest token is counted for every gap
+        config.policy.max_length = 36;
+        config.separator_type = SeparatorType::Tokens;
+        config.separator_tokens = vec!["-".to_owned(), " and ".to_owned()];
+        assert_eq!(config.word_length_budgets().unwrap(), [3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn test_groups() {
+        let config = ConfigBuilder::new().build().unwrap();
+        assert_eq!(config.group_total(), 0);
+        let mut config = ConfigBuilder::new()
+            .group_placement(Some("every-gap".to_owned()))
+            .group_symbols(Some("2".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.group_length(), 3);
+        assert_eq!(config.group_total(), 3);
+        config.group_placement = GroupPlacement::RandomGaps;
+        assert_eq!(config.group_total(), 1);
+        // 2 digits, a group of 3, 5 separators and 2 padding characters leave 22 for 4 words
+        config.policy.max_length = 34;
+        assert_eq!(config.word_length_budgets().unwrap(), [6, 6, 5, 5]);
+        config.word_count = 1;
+        assert_eq!(config.group_total(), 0);
+        let err = ConfigBuilder::new()
+            .group_placement(Some("random-gaps".to_owned()))
+            .group_count(Some("4".to_owned()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a group_count of 4 is more than the 3 gaps between 4 words"
+        );
+        let err = ConfigBuilder::new()
+            .group_placement(Some("every-gap".to_owned()))
+            .group_digits(Some("0".to_owned()))
+            .group_symbols(Some("1".to_owned()))
+            .symbol_characters(Some(String::new()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "groups between words need at least one digit or symbol"
+        );
+    }
+
+    #[test]
+    fn test_word_transformation_chain() {
+        let chain = |value: &str| {
+            ConfigBuilder::new()
+                .word_transformation(Some(value.to_owned()))
+                .build()
+                .map(|config| config.word_transformation)
+        };
+        assert_eq!(
+            chain("Capitalize-First, leet,,reverse,leet").unwrap(),
+            [
+                WordTransformationType::CapitalizeFirst,
+                WordTransformationType::Leet,
+                WordTransformationType::Reverse,
+                WordTransformationType::Leet,
+            ]
+        );
+        assert_eq!(chain("none").unwrap(), []);
+        assert_eq!(
+            chain("upper,none").unwrap(),
+            [WordTransformationType::Upper]
+        );
+        assert_eq!(chain("").unwrap(), []);
+        assert!(matches!(
+            chain("upper,shout").unwrap_err(),
+            ValidationError::InvalidEnum(_)
+        ));
+    }
+
+    #[test]
+    fn test_leet_table() {
+        let config = ConfigBuilder::new()
+            .word_transformation(Some("Random-Leet".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.word_transformation,
+            [WordTransformationType::RandomLeet]
+        );
+        assert_eq!(config.leet_table, default::LEET_TABLE);
+        let config = ConfigBuilder::new()
+            .leet_table(Some("a4,s$,o0".to_owned()))
+            .banned_characters(Some("$".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.leet_table, [('a', '4'), ('o', '0')]);
+        let error = |table: &str| {
+            ConfigBuilder::new()
+                .leet_table(Some(table.to_owned()))
+                .build()
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("a4,e"),
+            "`e` in the leet table must be a character followed by its substitute"
+        );
+        assert_eq!(
+            error("a4,A@"),
+            "the leet table substitutes `A` more than once"
+        );
+        assert_eq!(
+            error("ä4,Ä@"),
+            "the leet table substitutes `Ä` more than once"
+        );
+        assert_eq!(
+            ConfigBuilder::new()
+                .leet_table(Some("s$".to_owned()))
+                .banned_characters(Some("$".to_owned()))
+                .build()
+                .unwrap_err()
+                .to_string(),
+            "every leet substitution is banned by the password policy"
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let config = ConfigBuilder::new()
+            .template(Some(String::new()))
+            .build()
+            .unwrap();
+        assert_eq!(config.template, None);
+        // words are not fitted into the max_length of a template
+        let config = ConfigBuilder::new()
+            .template(Some("{word}{d}".to_owned()))
+            .max_length(Some("8".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.template.unwrap().to_string(), "{word}{d}");
+        let err = ConfigBuilder::new()
+            .template(Some("{word}{digit}".to_owned()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`{digit}` at character 7 of the template is not a valid token"
+        );
+    }
+
+    #[test]
+    fn test_separator_tokens() {
+        let config = ConfigBuilder::new()
+            .separator_tokens(Some(" and ,::,,-,::".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.separator_type, SeparatorType::Single);
+        assert_eq!(config.separator_tokens, [" and ", "-", "::"]);
+        assert_eq!(config.separator_choices(), default::SYMBOL_ALPHABET.len());
+        assert_eq!(config.separator_max_length(), 1);
+        let config = ConfigBuilder::new()
+            .separator_type(Some("tokens".to_owned()))
+            .separator_tokens(Some(" and ,::,-".to_owned()))
+            .banned_characters(Some("-".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.separator_tokens, [" and ", "::"]);
+        assert_eq!(config.separator_choices(), 2);
+        assert_eq!(config.separator_max_length(), 5);
+        let err = ConfigBuilder::new()
+            .separator_tokens(Some("-,--".to_owned()))
+            .banned_characters(Some("-".to_owned()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "every separator token is banned by the password policy"
+        );
+    }
+
+    #[test]
+    fn test_max_length_is_validated() {
+        ConfigBuilder::new()
+            .max_length(Some("19".to_owned()))
+            .build()
+            .unwrap_err();
+        let err = ConfigBuilder::new()
+            .max_length(Some("30".to_owned()))
+            .padding_type(Some("adaptive".to_owned()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a padding_length of 42 is longer than the max_length of 30"
+        );
+    }
+
+    #[test]
+    fn test_symmetric_padding_needs_enough_characters() {
+        let config = ConfigBuilder::new()
+            .padding_type(Some("symmetric".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(config.padding_length, default::PADDING_LENGTH_SYMMETRIC);
+        let err = ConfigBuilder::new()
+            .padding_type(Some("symmetric".to_owned()))
+            .padding_length(Some("3".to_owned()))
+            .padding_characters(Some("[]".to_owned()))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "symmetric padding of length 3 needs at least 3 padding characters to choose from, only 2 were given"
+        );
+    }
+
     #[test]
     fn test_padding_length_default_changes_with_padding_type() {
         let config = ConfigBuilder::new()
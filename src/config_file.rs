@@ -0,0 +1,381 @@
+//! Load configuration from files and the environment.
+//!
+//! Config files use a small subset of TOML, one `key = value` pair per line:
+//!
+//! ```toml
+//! # comments are allowed
+//! word_count = 5
+//! word_transformation = "capitalize-first"
+//! separator_characters = "-_."
+//! ```
+//!
+//! Keys are the names of the [`ConfigBuilder`] fields.
+//! Values may be basic `"..."` strings, literal `'...'` strings, or bare values such as integers.
+//!
+//! Environment variables use the same names, upper-cased and prefixed with [`ENV_PREFIX`],
+//! for example `FMN_PASSGEN_WORD_COUNT=5`.
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::config::ConfigBuilder;
+use crate::types::ValidationError;
+
+/// Prefix for environment variables which set configuration values.
+pub const ENV_PREFIX: &str = "FMN_PASSGEN_";
+/// The name of the directory, inside the platform's config directory, that holds our files.
+pub const APP_DIR_NAME: &str = "fmn-passgen";
+/// The name of the config file that is loaded by default.
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Find the directory that holds our files.
+///
+/// - Windows: `%APPDATA%\fmn-passgen`
+/// - everywhere else: `$XDG_CONFIG_HOME/fmn-passgen`, falling back to `$HOME/.config/fmn-passgen`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            // the XDG spec says relative paths are invalid and should be ignored
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|path| path.join(APP_DIR_NAME))
+}
+
+/// The path of the config file that is loaded when none is given explicitly.
+pub fn default_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+/// Load the default config file, if there is one.
+///
+/// A missing file is not an error and results in an empty [`ConfigBuilder`].
+pub fn load_default_config() -> Result<ConfigBuilder, ValidationError> {
+    match default_config_path() {
+        Some(path) if path.exists() => read_config_file(path),
+        _ => Ok(ConfigBuilder::new()),
+    }
+}
+
+/// Read and validate a config file.
+///
+/// Errors are prefixed with the file name.
+pub fn read_config_file(path: impl AsRef<Path>) -> Result<ConfigBuilder, ValidationError> {
+    let path = path.as_ref();
+    let in_file = |e| ValidationError::Context(path.display().to_string(), Box::new(e));
+    let contents = fs::read_to_string(path)
+        .map_err(|e: io::Error| in_file(ValidationError::Message(e.to_string())))?;
+    parse_config(&contents).map_err(in_file)
+}
+
+/// Parse and validate the contents of a config file.
+pub fn parse_config(contents: &str) -> Result<ConfigBuilder, ValidationError> {
+    let mut builder = ConfigBuilder::new();
+    let mut seen = HashSet::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let syntax_error =
+            |msg: &str| ValidationError::Message(format!("line {line_number}: {msg}"));
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            return Err(syntax_error("tables are not supported"));
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(syntax_error("expected `key = value`"));
+        };
+        let key = key.trim();
+        let value = parse_value(value.trim()).map_err(|msg| syntax_error(&msg))?;
+        if !seen.insert(key) {
+            return Err(syntax_error(&format!("`{key}` is set more than once")));
+        }
+        set_field(&mut builder, key, value, key)?;
+    }
+    Ok(builder)
+}
+
+/// Read configuration values from `FMN_PASSGEN_*` environment variables.
+///
+/// Errors are prefixed with the name of the variable.
+pub fn from_env() -> Result<ConfigBuilder, ValidationError> {
+    let mut builder = ConfigBuilder::new();
+    for name in ConfigBuilder::FIELD_NAMES {
+        let var = format!("{ENV_PREFIX}{}", name.to_ascii_uppercase());
+        if let Some(value) = env::var_os(&var) {
+            let value = value.into_string().map_err(|_| {
+                ValidationError::Context(
+                    var.clone(),
+                    Box::new(ValidationError::Message(
+                        "value is not valid unicode".to_owned(),
+                    )),
+                )
+            })?;
+            set_field(&mut builder, name, value, &var)?;
+        }
+    }
+    Ok(builder)
+}
+
+/// Write every value that is set in `builder` in the config file format.
+///
+/// The output can be read back with [`parse_config`].
+pub fn write_config(builder: &ConfigBuilder) -> String {
+    ConfigBuilder::FIELD_NAMES
+        .iter()
+        .filter_map(|name| {
+            let value = builder.field(name)?.as_deref()?;
+            Some(format!("{name} = {}\n", format_value(name, value)))
+        })
+        .collect()
+}
+
+/// Write the value of the setting `name` as a bare integer if it holds one, otherwise as a basic string.
+fn format_value(name: &str, value: &str) -> String {
+    if ConfigBuilder::INTEGER_FIELDS.contains(&name)
+        && let Ok(number) = value.parse::<u8>()
+    {
+        return number.to_string();
+    }
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Check `value` on its own and set it on `builder`.
+///
+/// Rules that depend on other settings are left to [`ConfigBuilder::build`],
+/// as another source may still change those settings.
+/// Errors are prefixed with `context`, the name the user knows the setting by.
+fn set_field(
+    builder: &mut ConfigBuilder,
+    name: &str,
+    value: String,
+    context: &str,
+) -> Result<(), ValidationError> {
+    let Some(field) = builder.field_mut(name) else {
+        return Err(ValidationError::UnknownKey(
+            name.to_owned(),
+            ConfigBuilder::FIELD_NAMES,
+        ));
+    };
+    ConfigBuilder::validate_field(name, &value)
+        .map_err(|e| ValidationError::Context(format!("`{context}`"), Box::new(e)))?;
+    *field = Some(value);
+    Ok(())
+}
+
+/// Parse the right hand side of a `key = value` line, dropping any trailing comment.
+fn parse_value(raw: &str) -> Result<String, String> {
+    let (value, rest) = if let Some(inner) = raw.strip_prefix('"') {
+        parse_basic_string(inner)?
+    } else if let Some(inner) = raw.strip_prefix('\'') {
+        let end = inner
+            .find('\'')
+            .ok_or_else(|| "unterminated string".to_owned())?;
+        (inner[..end].to_owned(), &inner[end + 1..])
+    } else {
+        let end = raw.find('#').unwrap_or(raw.len());
+        let value = raw[..end].trim();
+        if value.is_empty() {
+            return Err("missing value".to_owned());
+        }
+        (value.to_owned(), "")
+    };
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(value)
+    } else {
+        Err(format!("unexpected `{rest}` after value"))
+    }
+}
+
+/// Parse a TOML basic string, starting after the opening quote.
+///
+/// Returns the unescaped string and whatever follows the closing quote.
+fn parse_basic_string(inner: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut chars = inner.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &inner[i + 1..])),
+            '\\' => {
+                let escaped = match chars.next().map(|(_, c)| c) {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some(u @ ('u' | 'U')) => {
+                        let len = if u == 'u' { 4 } else { 8 };
+                        let hex = (0..len)
+                            .filter_map(|_| chars.next().map(|(_, c)| c))
+                            .collect::<String>();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .filter(|_| hex.len() == len)
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid unicode escape `\\{u}{hex}`"))?
+                    }
+                    Some(other) => return Err(format!("invalid escape `\\{other}`")),
+                    None => break,
+                };
+                value.push(escaped);
+            }
+            _ => value.push(c),
+        }
+    }
+    Err("unterminated string".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PaddingType;
+
+    #[test]
+    fn test_parse_config_ok() {
+        let contents = r#"
+            # a comment
+            word_count = 5 # trailing comment
+            padding_type = "adaptive"
+            separator_characters = "-\"\\ \u0041"
+            padding_characters = '#!'
+        "#;
+        let config = parse_config(contents).unwrap().build().unwrap();
+        assert_eq!(config.word_count, 5);
+        assert_eq!(config.padding_type, PaddingType::Adaptive);
+        assert_eq!(config.separator_characters, [' ', '"', '-', 'A', '\\']);
+        assert_eq!(config.padding_characters, ['!', '#']);
+    }
+
+    #[test]
+    fn test_parse_config_empty() {
+        let config = parse_config("").unwrap().build().unwrap();
+        assert_eq!(config, ConfigBuilder::new().build().unwrap());
+    }
+
+    #[test]
+    fn test_parse_config_unknown_key() {
+        let err = parse_config("word_cont = 5").unwrap_err();
+        assert!(matches!(err, ValidationError::UnknownKey(key, _) if key == "word_cont"));
+    }
+
+    #[test]
+    fn test_parse_config_invalid_value() {
+        let err = parse_config("\nword_count = 50").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`word_count`: `50` must be a positive integer between 0 and 32"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_checks_settings_together_on_build() {
+        let contents = "word_min_length = 1\nword_max_length = 2\nword_count = 2\nmax_length = 15";
+        let config = parse_config(contents).unwrap().build().unwrap();
+        assert_eq!(config.word_max_length, 2);
+        assert_eq!(config.policy.max_length, 15);
+
+        // another source may still lower the minimum
+        assert!(
+            parse_config("word_max_length = 2")
+                .unwrap()
+                .build()
+                .is_err()
+        );
+        let file = parse_config("word_max_length = 2").unwrap();
+        let cli = ConfigBuilder::new().word_min_length(Some("1".to_owned()));
+        assert_eq!(file.merge(cli).build().unwrap().word_max_length, 2);
+    }
+
+    #[test]
+    fn test_parse_config_syntax_errors() {
+        let cases = [
+            ("word_count", "line 1: expected `key = value`"),
+            ("word_count =", "line 1: missing value"),
+            ("[table]", "line 1: tables are not supported"),
+            ("\nrng_type = \"os-rng", "line 2: unterminated string"),
+            (
+                "rng_type = \"os\" rng",
+                "line 1: unexpected `rng` after value",
+            ),
+            ("rng_type = \"\\q\"", "line 1: invalid escape `\\q`"),
+            (
+                "count = 1\ncount = 2",
+                "line 2: `count` is set more than once",
+            ),
+        ];
+        for (contents, expected) in cases {
+            let err = parse_config(contents).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_read_config_file_reports_path() {
+        let dir = env::temp_dir().join(format!("fmn-passgen-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bad.toml");
+        fs::write(&path, "padding_type = \"sideways\"\n").unwrap();
+        let err = read_config_file(&path).unwrap_err().to_string();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(err.starts_with(&format!("{}: `padding_type`: ", path.display())));
+    }
+
+    #[test]
+    fn test_write_config_round_trip() {
+        let builder = ConfigBuilder::new()
+            .word_count(Some("5".to_owned()))
+            .padding_characters(Some("\"\\\t\u{7}#".to_owned()))
+            .separator_characters(Some(String::new()));
+        let written = write_config(&builder);
+        assert_eq!(
+            written,
+            "word_count = 5\npadding_characters = \"\\\"\\\\\\t\\u0007#\"\nseparator_characters = \"\"\n"
+        );
+        let parsed = parse_config(&written).unwrap();
+        assert_eq!(parsed.build().unwrap(), builder.build().unwrap());
+    }
+
+    #[test]
+    fn test_write_config_quotes_digit_strings() {
+        let builder = ConfigBuilder::new()
+            .word_count(Some("05".to_owned()))
+            .padding_characters(Some("01".to_owned()));
+        let written = write_config(&builder);
+        assert_eq!(written, "word_count = 5\npadding_characters = \"01\"\n");
+        let parsed = parse_config(&written).unwrap();
+        assert_eq!(parsed.build().unwrap(), builder.build().unwrap());
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let file = parse_config("word_count = 5\ncount = 2").unwrap();
+        let cli = ConfigBuilder::new().word_count(Some("6".to_owned()));
+        let config = file.merge(cli).build().unwrap();
+        assert_eq!(config.word_count, 6);
+        assert_eq!(config.count, 2);
+    }
+}
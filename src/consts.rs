@@ -3,18 +3,44 @@
 /// 0-9
 pub const DIGIT_ALPHABET: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
 
+/// The most words a password may be made of.
+pub const WORD_COUNT_MAX: u8 = 32;
+
 /// Configuration defaults.
 pub mod default {
     /// !@$%^&*-_+=:|~?/.
     pub const SYMBOL_ALPHABET: [char; 18] = [
         '!', '@', '$', '%', '^', '&', '*', '-', '_', '+', '=', ':', '|', '~', '?', '/', '.', ';',
     ];
+    /// " & ", " and ", "+", "-", "..", "//", "::", "~"
+    pub const SEPARATOR_TOKENS: [&str; 8] = [" & ", " and ", "+", "-", "..", "//", "::", "~"];
+    /// a4,e3,i1,o0,s$,t7
+    pub const LEET_TABLE: [(char, char); 6] = [
+        ('a', '4'),
+        ('e', '3'),
+        ('i', '1'),
+        ('o', '0'),
+        ('s', '$'),
+        ('t', '7'),
+    ];
     pub const COUNT: u8 = 1;
     pub const WORD_COUNT: u8 = 4;
     pub const WORD_MIN_LENGTH: u8 = 3;
     pub const WORD_MAX_LENGTH: u8 = 11;
     pub const DIGITS_BEFORE: u8 = 0;
     pub const DIGITS_AFTER: u8 = 2;
+    /// a coin is flipped for every letter
+    pub const UPPERCASE_LIMIT: u8 = 0;
+    pub const GROUP_COUNT: u8 = 1;
+    pub const GROUP_DIGITS: u8 = 1;
+    pub const GROUP_SYMBOLS: u8 = 0;
     pub const PADDING_LENGTH_FIXED: u8 = 1;
     pub const PADDING_LENGTH_ADAPTIVE: u8 = 42;
+    pub const PADDING_LENGTH_SYMMETRIC: u8 = 2;
+    /// no minimum
+    pub const MIN_ENTROPY: u8 = 0;
+    /// no maximum
+    pub const MAX_LENGTH: u8 = 0;
+    /// no maximum
+    pub const MAX_REPEATED: u8 = 0;
 }
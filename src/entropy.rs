@@ -0,0 +1,570 @@
+//! Estimate the strength of generated passwords.
+//!
+//! Entropy is measured in bits, assuming an attacker knows the [`Config`] and the wordlist
+//! used to create a password, but not the random choices that were made.
+use std::fmt;
+
+use crate::config::Config;
+use crate::consts::DIGIT_ALPHABET;
+use crate::template::Template;
+use crate::template::Token;
+use crate::types::GroupPlacement;
+use crate::types::PaddingMode;
+use crate::types::PaddingType;
+use crate::types::SeparatorType;
+use crate::types::UppercaseLimitType;
+use crate::types::WordTransformationType;
+use crate::word_transformer;
+
+/// The bits of entropy contributed by each part of a password.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Entropy {
+    /// choosing words from the filtered wordlist
+    pub words: f64,
+    /// randomly transforming the chosen words
+    pub word_transformation: f64,
+    /// choosing digits for the pseudo-words
+    pub digits: f64,
+    /// choosing the gaps between words for groups, and the digits and symbols in them,
+    /// or choosing the symbols of a template
+    pub groups: f64,
+    /// choosing the separators
+    pub separator: f64,
+    /// choosing the padding characters
+    pub padding: f64,
+    /// lost by throwing away passwords that break the [`crate::policy::Policy`],
+    /// which is subtracted from the total
+    pub policy: f64,
+}
+
+impl Entropy {
+    /// Estimate the entropy of any password made with `config`.
+    ///
+    /// `wordlist_len` is the number of words left after filtering by length,
+    /// see [`crate::password_maker::PasswordMaker::filter_wordlist`].
+    ///
+    /// This is a lower bound: [`PaddingType::Adaptive`] padding is only counted
+    /// when every possible password would be padded,
+    /// and then only as many characters as the longest password would be padded with.
+    /// The cost of the policy is left at 0, as it depends on the wordlist,
+    /// see [`crate::password_maker::PasswordMaker::policy_cost`].
+    pub fn estimate(config: &Config, wordlist_len: usize) -> Self {
+        let padding_length = config.padding_length as usize;
+        let padding_count = match config.padding_type {
+            PaddingType::None => 0,
+            PaddingType::Fixed | PaddingType::Symmetric => 2 * padding_length,
+            PaddingType::Adaptive => {
+                padding_length.saturating_sub(max_unpadded_length(config, wordlist_len))
+            }
+        };
+        Self::with_padding(config, wordlist_len, padding_count)
+    }
+    /// Calculate the entropy of a password made with `config`,
+    /// given how many padding characters were actually added to it.
+    ///
+    /// A template decides how much padding there is, so `padding_count` is then ignored.
+    pub(crate) fn with_padding(config: &Config, wordlist_len: usize, padding_count: usize) -> Self {
+        if let Some(template) = &config.template {
+            return Self::with_template(config, template, wordlist_len);
+        }
+        let word_count = if wordlist_len == 0 {
+            0
+        } else {
+            config.word_count as usize
+        };
+        let words = word_count as f64 * bits(wordlist_len);
+        let word_transformation = if counts_random_upper_lower(&config.word_transformation) {
+            word_count as f64
+        } else {
+            0.0
+        };
+        let digit_count = config.digits_before as usize + config.digits_after as usize;
+        let digits = digit_count as f64 * bits(DIGIT_ALPHABET.len());
+        let group_total = group_total(config, wordlist_len);
+        let group_positions = match config.group_placement {
+            GroupPlacement::RandomGaps => {
+                combination_bits(word_count.saturating_sub(1), group_total)
+            }
+            GroupPlacement::None | GroupPlacement::EveryGap => 0.0,
+        };
+        let group_symbols = if config.symbol_characters.is_empty() {
+            0
+        } else {
+            config.group_symbols as usize
+        };
+        let groups = group_positions
+            + (group_total * config.group_digits as usize) as f64 * bits(DIGIT_ALPHABET.len())
+            + (group_total * group_symbols) as f64 * bits(config.symbol_characters.len());
+        let gaps = part_count(config, wordlist_len).saturating_sub(1);
+        let separator = match config.separator_type {
+            _ if gaps == 0 => 0.0,
+            SeparatorType::Single => bits(config.separator_choices()),
+            SeparatorType::PerGap | SeparatorType::Tokens => {
+                gaps as f64 * bits(config.separator_choices())
+            }
+        };
+        let padding = match (config.padding_type, config.padding_mode) {
+            _ if padding_count == 0 => 0.0,
+            (PaddingType::Symmetric, _) => ordered_choice_bits(
+                config.padding_characters.len(),
+                config.padding_length as usize,
+            ),
+            (_, PaddingMode::PerCharacter) => {
+                padding_count as f64 * bits(config.padding_characters.len())
+            }
+            (_, PaddingMode::Single) => bits(config.padding_characters.len()),
+        };
+        Self {
+            words,
+            word_transformation,
+            digits,
+            groups,
+            separator,
+            padding,
+            policy: 0.0,
+        }
+    }
+    /// Calculate the entropy of a password made from `template`.
+    fn with_template(config: &Config, template: &Template, wordlist_len: usize) -> Self {
+        let separators = template.count(|token| *token == Token::Separator);
+        let separator = match config.separator_type {
+            _ if separators == 0 => 0.0,
+            SeparatorType::Single => bits(config.separator_choices()),
+            SeparatorType::PerGap | SeparatorType::Tokens => {
+                separators as f64 * bits(config.separator_choices())
+            }
+        };
+        let paddings = template.count(|token| *token == Token::Padding);
+        let padding = match config.padding_mode {
+            _ if paddings == 0 => 0.0,
+            PaddingMode::Single => bits(config.padding_characters.len()),
+            PaddingMode::PerCharacter => paddings as f64 * bits(config.padding_characters.len()),
+        };
+        let symbols = template.count(|token| *token == Token::Symbol);
+        Self {
+            words: template.word_count() as f64 * bits(wordlist_len),
+            word_transformation: 0.0,
+            digits: template.digit_count() as f64 * bits(DIGIT_ALPHABET.len()),
+            groups: symbols as f64 * bits(config.symbol_characters.len()),
+            separator,
+            padding,
+            policy: 0.0,
+        }
+    }
+    /// Replace the entropy of the words with that of choosing each word from
+    /// its own number of words, as when they must fit in a maximum length,
+    /// see [`Config::word_length_budgets`].
+    pub(crate) fn with_word_choices(self, choices: &[usize]) -> Self {
+        Self {
+            words: choices.iter().map(|n| bits(*n)).sum(),
+            ..self
+        }
+    }
+    /// Add one bit to the entropy of the word transformations for each of `substitutable` characters,
+    /// which [`WordTransformationType::RandomLeet`] may or may not substitute.
+    pub(crate) fn with_leet_substitutions(self, substitutable: usize) -> Self {
+        Self {
+            word_transformation: self.word_transformation + substitutable as f64,
+            ..self
+        }
+    }
+    /// Add the entropy of [`WordTransformationType::RandomCase`] deciding the case of
+    /// `letters` letters in each word, see [`random_case_letters`].
+    ///
+    /// Without an uppercase_limit every letter is worth a bit,
+    /// otherwise it is the number of ways to choose the letters to uppercase.
+    pub(crate) fn with_random_case(self, config: &Config, letters: &[usize]) -> Self {
+        let limit = config.uppercase_limit as usize;
+        let bits = match config.uppercase_limit_type {
+            _ if limit == 0 => letters.iter().sum::<usize>() as f64,
+            UppercaseLimitType::Word => letters
+                .iter()
+                .map(|n| combination_bits(*n, limit.min(*n)))
+                .sum(),
+            UppercaseLimitType::Password => {
+                let total = letters.iter().sum();
+                combination_bits(total, limit.min(total))
+            }
+        };
+        Self {
+            word_transformation: self.word_transformation + bits,
+            ..self
+        }
+    }
+    /// The sum of every part's entropy, less the cost of the policy.
+    pub fn total(&self) -> f64 {
+        self.words
+            + self.word_transformation
+            + self.digits
+            + self.groups
+            + self.separator
+            + self.padding
+            - self.policy
+    }
+}
+
+/// Displays the total, rounded down to one decimal place so that it never overstates strength.
+impl fmt::Display for Entropy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = (self.total() * 10.0).floor() / 10.0;
+        write!(f, "{total:.1} bits")
+    }
+}
+
+/// Bits of entropy in a uniform choice between `n` options.
+fn bits(n: usize) -> f64 {
+    if n == 0 { 0.0 } else { (n as f64).log2() }
+}
+
+/// Bits of entropy in choosing `k` distinct items in order from `n` options,
+/// choosing as many as possible if `k` is more than `n`.
+fn ordered_choice_bits(n: usize, k: usize) -> f64 {
+    (n.saturating_sub(k)..n).map(|i| bits(i + 1)).sum()
+}
+
+/// Bits of entropy in choosing `k` distinct items in any order from `n` options.
+fn combination_bits(n: usize, k: usize) -> f64 {
+    ordered_choice_bits(n, k) - ordered_choice_bits(k, k)
+}
+
+/// Whether `step` sets the case of every letter, undoing any random case before it.
+fn resets_case(step: WordTransformationType) -> bool {
+    matches!(
+        step,
+        WordTransformationType::Lower
+            | WordTransformationType::Upper
+            | WordTransformationType::AlternatingLowerUpper
+            | WordTransformationType::AlternatingUpperLower
+            | WordTransformationType::RandomUpperLower
+            | WordTransformationType::RandomCase
+    )
+}
+
+/// Whether `step` changes the case of any letter.
+fn changes_case(step: WordTransformationType) -> bool {
+    resets_case(step)
+        || matches!(
+            step,
+            WordTransformationType::CapitalizeFirst
+                | WordTransformationType::CapitalizeLast
+                | WordTransformationType::CapitalizeNotFirst
+        )
+}
+
+/// Whether `step` substitutes characters from the leet table.
+fn substitutes(step: WordTransformationType) -> bool {
+    matches!(
+        step,
+        WordTransformationType::Leet | WordTransformationType::RandomLeet
+    )
+}
+
+/// Whether a [`WordTransformationType::RandomUpperLower`] in `chain` is worth a bit per word,
+/// which it is unless a later step sets the case again.
+fn counts_random_upper_lower(chain: &[WordTransformationType]) -> bool {
+    chain
+        .iter()
+        .rev()
+        .find(|step| resets_case(**step))
+        .is_some_and(|step| *step == WordTransformationType::RandomUpperLower)
+}
+
+/// Whether a [`WordTransformationType::RandomLeet`] in `chain` is worth a bit for every character
+/// it could substitute, which it is only if no other step substitutes characters.
+pub(crate) fn counts_random_leet(chain: &[WordTransformationType]) -> bool {
+    let mut substitutions = chain.iter().filter(|step| substitutes(**step));
+    substitutions
+        .next()
+        .is_some_and(|step| *step == WordTransformationType::RandomLeet)
+        && substitutions.next().is_none()
+}
+
+/// Whether a [`WordTransformationType::RandomCase`] in `chain` is worth anything,
+/// which it is unless a later step changes the case of any letter.
+pub(crate) fn counts_random_case(chain: &[WordTransformationType]) -> bool {
+    chain
+        .iter()
+        .rev()
+        .find(|step| changes_case(**step))
+        .is_some_and(|step| *step == WordTransformationType::RandomCase)
+}
+
+/// How many letters of `word` [`WordTransformationType::RandomCase`] is counted as deciding the case of,
+/// leaving out any that a leet step in the chain could substitute.
+pub(crate) fn random_case_letters(config: &Config, word: &str) -> usize {
+    let table = if config
+        .word_transformation
+        .iter()
+        .any(|step| substitutes(*step))
+    {
+        config.leet_table.as_slice()
+    } else {
+        &[]
+    };
+    word_transformer::random_case_letters(word, table)
+}
+
+/// The number of groups placed between words, which needs words to place them between.
+fn group_total(config: &Config, wordlist_len: usize) -> usize {
+    if wordlist_len == 0 {
+        0
+    } else {
+        config.group_total()
+    }
+}
+
+/// The number of words and non-empty pseudo-words that separators are placed between.
+fn part_count(config: &Config, wordlist_len: usize) -> usize {
+    let words = if wordlist_len == 0 {
+        0
+    } else {
+        config.word_count as usize
+    };
+    words
+        + group_total(config, wordlist_len)
+        + usize::from(config.digits_before > 0)
+        + usize::from(config.digits_after > 0)
+}
+
+/// The longest a password made with `config` could possibly be before padding.
+fn max_unpadded_length(config: &Config, wordlist_len: usize) -> usize {
+    let words = if wordlist_len == 0 {
+        0
+    } else {
+        config.word_count as usize * config.word_max_length as usize
+    };
+    let digits = config.digits_before as usize
+        + config.digits_after as usize
+        + group_total(config, wordlist_len) * config.group_length();
+    let separators =
+        part_count(config, wordlist_len).saturating_sub(1) * config.separator_max_length();
+    words + digits + separators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_estimate_default() {
+        let config = ConfigBuilder::new().build().unwrap();
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(entropy.words, 4.0 * 7776f64.log2());
+        assert_close(entropy.word_transformation, 0.0);
+        assert_close(entropy.digits, 2.0 * 10f64.log2());
+        assert_close(entropy.separator, 18f64.log2());
+        assert_close(entropy.padding, 18f64.log2());
+        assert_eq!(entropy.to_string(), "66.6 bits");
+    }
+
+    #[test]
+    fn test_estimate_random_upper_lower() {
+        let config = ConfigBuilder::new()
+            .word_transformation(Some("random-upper-lower".to_owned()))
+            .build()
+            .unwrap();
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(entropy.word_transformation, 4.0);
+    }
+
+    #[test]
+    fn test_estimate_transformation_chain() {
+        let estimate = |chain: &str| {
+            let config = ConfigBuilder::new()
+                .word_transformation(Some(chain.to_owned()))
+                .build()
+                .unwrap();
+            Entropy::estimate(&config, 7776).word_transformation
+        };
+        assert_close(estimate("random-upper-lower,capitalize-first,reverse"), 4.0);
+        // a later step that sets the case undoes the random case
+        assert_close(estimate("random-upper-lower,lower"), 0.0);
+        assert_close(estimate("upper,random-upper-lower"), 4.0);
+        assert_close(estimate("none"), 0.0);
+        let chain = |chain: &str| {
+            ConfigBuilder::new()
+                .word_transformation(Some(chain.to_owned()))
+                .build()
+                .unwrap()
+                .word_transformation
+        };
+        assert!(counts_random_leet(&chain("upper,random-leet,reverse")));
+        assert!(!counts_random_leet(&chain("leet,random-leet")));
+        assert!(!counts_random_leet(&chain("random-leet,random-leet")));
+        assert!(!counts_random_leet(&chain("random-upper-lower")));
+    }
+
+    #[test]
+    fn test_random_case() {
+        let chain = |chain: &str| {
+            ConfigBuilder::new()
+                .word_transformation(Some(chain.to_owned()))
+                .build()
+                .unwrap()
+        };
+        assert!(counts_random_case(
+            &chain("upper,random-case,reverse").word_transformation
+        ));
+        assert!(!counts_random_case(
+            &chain("random-case,capitalize-first").word_transformation
+        ));
+        assert!(!counts_random_case(
+            &chain("random-upper-lower").word_transformation
+        ));
+        // random-case undoes random-upper-lower
+        assert!(!counts_random_upper_lower(
+            &chain("random-upper-lower,random-case").word_transformation
+        ));
+        assert_eq!(random_case_letters(&chain("random-case"), "horse"), 5);
+        assert_eq!(random_case_letters(&chain("leet,random-case"), "horse"), 2);
+
+        let mut config = chain("random-case");
+        let entropy = Entropy::default().with_random_case(&config, &[5, 3]);
+        assert_close(entropy.word_transformation, 8.0);
+        config.uppercase_limit = 2;
+        let entropy = Entropy::default().with_random_case(&config, &[5, 1]);
+        // 10 ways to choose 2 of 5 letters, and the only letter of the other word
+        assert_close(entropy.word_transformation, 10f64.log2());
+        config.uppercase_limit_type = UppercaseLimitType::Password;
+        let entropy = Entropy::default().with_random_case(&config, &[5, 1]);
+        assert_close(entropy.word_transformation, 15f64.log2());
+    }
+
+    #[test]
+    fn test_estimate_no_separator_between_single_part() {
+        let config = ConfigBuilder::new()
+            .word_count(Some("1".to_owned()))
+            .digits_after(Some("0".to_owned()))
+            .build()
+            .unwrap();
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(entropy.separator, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_separators_per_gap() {
+        let config = ConfigBuilder::new()
+            .separator_type(Some("per-gap".to_owned()))
+            .build()
+            .unwrap();
+        // 4 words and 1 pseudo-word leave 4 gaps
+        assert_close(
+            Entropy::estimate(&config, 7776).separator,
+            4.0 * 18f64.log2(),
+        );
+        let config = ConfigBuilder::new()
+            .separator_type(Some("tokens".to_owned()))
+            .build()
+            .unwrap();
+        assert_close(Entropy::estimate(&config, 7776).separator, 4.0 * 3.0);
+    }
+
+    #[test]
+    fn test_estimate_groups() {
+        let mut config = ConfigBuilder::new()
+            .group_placement(Some("every-gap".to_owned()))
+            .group_symbols(Some("1".to_owned()))
+            .build()
+            .unwrap();
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(entropy.groups, 3.0 * (10f64.log2() + 18f64.log2()));
+        // 3 groups add 3 gaps for separators, but the same separator goes in every gap
+        assert_close(entropy.separator, 18f64.log2());
+        // 2 of 3 gaps can be chosen in 3 ways
+        config.group_placement = GroupPlacement::RandomGaps;
+        config.group_count = 2;
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(
+            entropy.groups,
+            3f64.log2() + 2.0 * (10f64.log2() + 18f64.log2()),
+        );
+        assert_close(Entropy::estimate(&config, 0).groups, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_template() {
+        let mut config = ConfigBuilder::new()
+            .template(Some(
+                "{Word}{sep}{word}{sep}{dd}{sym}{WORD}{pad}{pad}".to_owned(),
+            ))
+            .build()
+            .unwrap();
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(entropy.words, 3.0 * 7776f64.log2());
+        assert_close(entropy.digits, 2.0 * 10f64.log2());
+        assert_close(entropy.groups, 18f64.log2());
+        assert_close(entropy.separator, 18f64.log2());
+        assert_close(entropy.padding, 18f64.log2());
+        config.separator_type = SeparatorType::PerGap;
+        config.padding_mode = PaddingMode::PerCharacter;
+        let entropy = Entropy::estimate(&config, 7776);
+        assert_close(entropy.separator, 2.0 * 18f64.log2());
+        assert_close(entropy.padding, 2.0 * 18f64.log2());
+    }
+
+    #[test]
+    fn test_estimate_empty_wordlist() {
+        let config = ConfigBuilder::new().build().unwrap();
+        let entropy = Entropy::estimate(&config, 0);
+        assert_close(entropy.words, 0.0);
+        assert_close(entropy.separator, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_adaptive_padding() {
+        let mut config = ConfigBuilder::new()
+            .padding_type(Some("adaptive".to_owned()))
+            .build()
+            .unwrap();
+        // 4 * 11 + 2 + 4 separators can never reach 255
+        config.padding_length = 255;
+        assert_close(Entropy::estimate(&config, 7776).padding, 18f64.log2());
+        // but might reach 42
+        config.padding_length = 42;
+        assert_close(Entropy::estimate(&config, 7776).padding, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_per_character_padding() {
+        let mut config = ConfigBuilder::new()
+            .padding_mode(Some("per-character".to_owned()))
+            .padding_length(Some("2".to_owned()))
+            .build()
+            .unwrap();
+        assert_close(Entropy::estimate(&config, 7776).padding, 4.0 * 18f64.log2());
+        // the longest password, 4 * 11 + 2 + 4 separators, is padded with 5 characters
+        config.padding_type = PaddingType::Adaptive;
+        config.padding_length = 55;
+        assert_close(Entropy::estimate(&config, 7776).padding, 5.0 * 18f64.log2());
+        config.padding_length = 42;
+        assert_close(Entropy::estimate(&config, 7776).padding, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_symmetric_padding() {
+        let mut config = ConfigBuilder::new()
+            .padding_type(Some("symmetric".to_owned()))
+            .padding_length(Some("3".to_owned()))
+            .build()
+            .unwrap();
+        // 18 * 17 * 16 orders of 3 distinct characters
+        assert_close(Entropy::estimate(&config, 7776).padding, 4896f64.log2());
+        // asking for more characters than there are still only uses each once
+        config.padding_characters = vec!['[', '('];
+        assert_close(Entropy::estimate(&config, 7776).padding, 1.0);
+    }
+
+    #[test]
+    fn test_estimate_no_padding() {
+        let config = ConfigBuilder::new()
+            .padding_type(Some("none".to_owned()))
+            .build()
+            .unwrap();
+        assert_close(Entropy::estimate(&config, 7776).padding, 0.0);
+    }
+}
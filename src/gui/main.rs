@@ -13,12 +13,30 @@ use rand::SeedableRng;
 use rand::TryRngCore;
 use rand::rngs::OsRng;
 use rand::rngs::SmallRng;
+use rand::rngs::ThreadRng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha20Rng;
 
 use fmn_passgen::config::Config;
 use fmn_passgen::config::ConfigBuilder;
+use fmn_passgen::config::validate_leet_table;
+use fmn_passgen::consts::WORD_COUNT_MAX;
+use fmn_passgen::password_maker::GeneratedPassword;
 use fmn_passgen::password_maker::PasswordMaker;
+use fmn_passgen::password_maker::default_wordlist;
+use fmn_passgen::rng::PasswordRng;
+use fmn_passgen::types::GroupPlacement;
+use fmn_passgen::types::PaddingMode;
 use fmn_passgen::types::PaddingType;
+use fmn_passgen::types::RngType;
+use fmn_passgen::types::SeparatorType;
 use fmn_passgen::types::StrEnum;
+use fmn_passgen::types::UppercaseLimitType;
+use fmn_passgen::types::ValidationError;
 use fmn_passgen::types::WordTransformationType;
 
 const INITIAL_SEED: u64 = 13414357264162109690;
@@ -31,12 +49,18 @@ const COMPACT_SPACING: f32 = 3.0;
 struct App {
     config_prev: Config,
     config_curr: Config,
-    password_maker: PasswordMaker<OsRng>,
     preview_maker: PasswordMaker<SmallRng>,
-    preview: String,
+    preview: GeneratedPassword,
+    min_entropy_error: Option<ValidationError>,
+    preview_error: Option<ValidationError>,
     generated_passwords: Vec<String>,
+    generate_error: Option<ValidationError>,
+    leet_table: String,
+    leet_table_error: Option<ValidationError>,
+    symbol_characters: String,
     padding_characters: String,
     separator_characters: String,
+    separator_tokens: String,
     seed_string: String,
     curr_seed: u64,
     prev_seed: u64,
@@ -50,32 +74,44 @@ impl App {
         });
         let config_prev = ConfigBuilder::new().build().unwrap();
         let config_curr = config_prev.clone();
-        // TODO take references to a config?
-        let password_maker = PasswordMaker::<OsRng>::new(config_curr.clone());
         let seed_string = INITIAL_SEED.to_string();
         let curr_seed = INITIAL_SEED;
         let prev_seed = INITIAL_SEED;
-        let mut preview_maker: PasswordMaker<SmallRng> = PasswordMaker {
-            rng: SmallRng::seed_from_u64(curr_seed).unwrap_err(),
-            config: config_curr.clone(),
-            wordlist: WORDLIST.iter().map(|s| String::from(*s)).collect(),
-        };
-        let preview: String = preview_maker.make_password();
+        let mut preview_maker: PasswordMaker<SmallRng> = PasswordMaker::from_rng(
+            SmallRng::seed_from_u64(curr_seed),
+            config_curr.clone(),
+            WORDLIST.iter().map(|s| String::from(*s)).collect(),
+        );
+        let preview: GeneratedPassword = preview_maker.generate_password().unwrap();
         let generated_passwords: Vec<String> = Vec::new();
+        let leet_table: String = config_curr
+            .leet_table
+            .iter()
+            .map(|(from, to)| format!("{from}{to}"))
+            .collect::<Vec<String>>()
+            .join(",");
+        let symbol_characters: String = config_curr.symbol_characters.iter().collect();
         let padding_characters: String = config_curr.padding_characters.iter().collect();
         let separator_characters: String = config_curr.separator_characters.iter().collect();
+        let separator_tokens: String = config_curr.separator_tokens.join(",");
         Self {
             config_prev,
             config_curr,
-            password_maker,
             seed_string,
             curr_seed,
             prev_seed,
             preview_maker,
             preview,
+            min_entropy_error: None,
+            preview_error: None,
             generated_passwords,
+            generate_error: None,
+            leet_table,
+            leet_table_error: None,
+            symbol_characters,
             padding_characters,
             separator_characters,
+            separator_tokens,
         }
     }
     fn show_inner(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
@@ -95,27 +131,48 @@ impl App {
                         }
                     }
                     if ui.button("random seed").clicked() {
-                        self.curr_seed = self.password_maker.rng.random();
+                        self.curr_seed = OsRng.unwrap_err().random();
                         self.seed_string = self.curr_seed.to_string();
                     }
                 });
                 if self.config_curr != self.config_prev || self.curr_seed != self.prev_seed {
                     self.preview_maker.rng = SmallRng::seed_from_u64(self.curr_seed).unwrap_err();
                     self.preview_maker.config = self.config_curr.clone();
-                    self.preview = self.preview_maker.make_password();
+                    self.min_entropy_error = self.preview_maker.resolve_min_entropy().err();
+                    self.config_curr.word_count = self.preview_maker.config.word_count;
+                    // keep showing the last password that could be made
+                    match self.preview_maker.generate_password() {
+                        Ok(preview) => {
+                            self.preview = preview;
+                            self.preview_error = None;
+                        }
+                        Err(e) => self.preview_error = Some(e),
+                    }
                     self.config_prev = self.config_curr.clone();
                     self.prev_seed = self.curr_seed;
                 }
-                ui.label(RichText::new(&self.preview).color(Color32::ORANGE));
+                ui.label(RichText::new(self.preview.to_string()).color(Color32::ORANGE));
+                ui.label(format!("entropy: {}", self.preview.entropy));
+                if let Some(e) = &self.preview_error {
+                    ui.label(RichText::new(e.to_string()).color(Color32::RED));
+                }
+                if let Some(e) = &self.min_entropy_error {
+                    ui.label(RichText::new(e.to_string()).color(Color32::RED));
+                }
             });
         egui::CollapsingHeader::new("words")
             .default_open(true)
             .show(ui, |ui| {
-                ui.add(
-                    egui::Slider::new(&mut self.config_curr.word_count, 0..=32)
+                ui.add_enabled(
+                    self.config_curr.min_entropy == 0,
+                    egui::Slider::new(&mut self.config_curr.word_count, 0..=WORD_COUNT_MAX)
                         .text("count")
                         .logarithmic(true),
                 );
+                ui.add(
+                    egui::Slider::new(&mut self.config_curr.min_entropy, 0..=255)
+                        .text("min entropy"),
+                );
                 ui.add(
                     egui::Slider::new(&mut self.config_curr.word_min_length, 3..=9)
                         .text("min length"),
@@ -127,14 +184,59 @@ impl App {
                     )
                     .text("max length"),
                 );
-                egui::ComboBox::from_label("transform")
-                    .selected_text(self.config_curr.word_transformation.to_static_str())
+                let mut removed = None;
+                for (i, step) in self.config_curr.word_transformation.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::new(i, format!("transform {}", i + 1))
+                            .selected_text(step.to_static_str())
+                            .show_ui(ui, |ui| {
+                                ui.style_mut().spacing.item_spacing =
+                                    egui::vec2(COMPACT_SPACING, COMPACT_SPACING);
+                                for (description, item) in WordTransformationType::NAME_MEMBER_ARR {
+                                    ui.selectable_value(step, *item, *description);
+                                }
+                            });
+                        if ui.button("remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = removed {
+                    self.config_curr.word_transformation.remove(i);
+                }
+                if ui.button("add transform").clicked() {
+                    self.config_curr
+                        .word_transformation
+                        .push(WordTransformationType::default());
+                }
+                ui.horizontal(|ui| {
+                    let name_label = ui.label("leet table");
+                    ui.text_edit_singleline(&mut self.leet_table)
+                        .labelled_by(name_label.id);
+                });
+                // keep the last table that was valid
+                match validate_leet_table(Some(self.leet_table.clone())) {
+                    Ok(leet_table) => {
+                        self.config_curr.leet_table = leet_table;
+                        self.leet_table_error = None;
+                    }
+                    Err(e) => self.leet_table_error = Some(e),
+                }
+                if let Some(e) = &self.leet_table_error {
+                    ui.label(RichText::new(e.to_string()).color(Color32::RED));
+                }
+                ui.add(
+                    egui::Slider::new(&mut self.config_curr.uppercase_limit, 0..=9)
+                        .text("uppercase limit"),
+                );
+                egui::ComboBox::from_label("uppercase limit type")
+                    .selected_text(self.config_curr.uppercase_limit_type.to_static_str())
                     .show_ui(ui, |ui| {
                         ui.style_mut().spacing.item_spacing =
                             egui::vec2(COMPACT_SPACING, COMPACT_SPACING);
-                        for (description, item) in WordTransformationType::NAME_MEMBER_ARR {
+                        for (description, item) in UppercaseLimitType::NAME_MEMBER_ARR {
                             ui.selectable_value(
-                                &mut self.config_curr.word_transformation,
+                                &mut self.config_curr.uppercase_limit_type,
                                 *item,
                                 *description,
                             );
@@ -155,6 +257,38 @@ impl App {
                         .logarithmic(true),
                 );
             });
+        egui::CollapsingHeader::new("groups between words")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ComboBox::from_label("placement")
+                    .selected_text(self.config_curr.group_placement.to_static_str())
+                    .show_ui(ui, |ui| {
+                        ui.style_mut().spacing.item_spacing =
+                            egui::vec2(COMPACT_SPACING, COMPACT_SPACING);
+                        for (description, item) in GroupPlacement::NAME_MEMBER_ARR {
+                            ui.selectable_value(
+                                &mut self.config_curr.group_placement,
+                                *item,
+                                *description,
+                            );
+                        }
+                    });
+                ui.add_enabled(
+                    self.config_curr.group_placement == GroupPlacement::RandomGaps,
+                    egui::Slider::new(&mut self.config_curr.group_count, 1..=WORD_COUNT_MAX)
+                        .text("count"),
+                );
+                ui.add(egui::Slider::new(&mut self.config_curr.group_digits, 0..=9).text("digits"));
+                ui.add(
+                    egui::Slider::new(&mut self.config_curr.group_symbols, 0..=9).text("symbols"),
+                );
+                ui.horizontal(|ui| {
+                    let name_label = ui.label("possible symbols");
+                    ui.text_edit_singleline(&mut self.symbol_characters)
+                        .labelled_by(name_label.id);
+                });
+                self.config_curr.symbol_characters = self.symbol_characters.chars().collect();
+            });
         egui::CollapsingHeader::new("padding")
             .default_open(true)
             .show(ui, |ui| {
@@ -177,6 +311,19 @@ impl App {
                             );
                         }
                     });
+                egui::ComboBox::from_label("mode")
+                    .selected_text(self.config_curr.padding_mode.to_static_str())
+                    .show_ui(ui, |ui| {
+                        ui.style_mut().spacing.item_spacing =
+                            egui::vec2(COMPACT_SPACING, COMPACT_SPACING);
+                        for (description, item) in PaddingMode::NAME_MEMBER_ARR {
+                            ui.selectable_value(
+                                &mut self.config_curr.padding_mode,
+                                *item,
+                                *description,
+                            );
+                        }
+                    });
                 ui.add(
                     egui::Slider::new(&mut self.config_curr.padding_length, 0..=255)
                         .text("length")
@@ -192,6 +339,44 @@ impl App {
                         .labelled_by(name_label.id);
                 });
                 self.config_curr.separator_characters = self.separator_characters.chars().collect();
+                ui.horizontal(|ui| {
+                    let name_label = ui.label("possible tokens");
+                    ui.text_edit_singleline(&mut self.separator_tokens)
+                        .labelled_by(name_label.id);
+                });
+                self.config_curr.separator_tokens = self
+                    .separator_tokens
+                    .split(',')
+                    .filter(|token| !token.is_empty())
+                    .map(str::to_owned)
+                    .collect();
+                egui::ComboBox::from_label("type")
+                    .selected_text(self.config_curr.separator_type.to_static_str())
+                    .show_ui(ui, |ui| {
+                        ui.style_mut().spacing.item_spacing =
+                            egui::vec2(COMPACT_SPACING, COMPACT_SPACING);
+                        for (description, item) in SeparatorType::NAME_MEMBER_ARR {
+                            ui.selectable_value(
+                                &mut self.config_curr.separator_type,
+                                *item,
+                                *description,
+                            );
+                        }
+                    });
+            });
+        egui::ComboBox::from_label("random number generator")
+            .selected_text(self.config_curr.rng_type.to_static_str())
+            .show_ui(ui, |ui| {
+                ui.style_mut().spacing.item_spacing = egui::vec2(COMPACT_SPACING, COMPACT_SPACING);
+                for (description, item) in RngType::NAME_MEMBER_ARR {
+                    #[cfg(feature = "dice")]
+                    if *item == RngType::Dice {
+                        // there is nowhere to enter dice rolls
+                        continue;
+                    }
+                    ui.selectable_value(&mut self.config_curr.rng_type, *item, *description)
+                        .on_hover_text(item.description());
+                }
             });
         ui.add(
             egui::Slider::new(&mut self.config_curr.count, 1..=255)
@@ -206,8 +391,19 @@ impl App {
             )
             .clicked()
         {
-            self.password_maker.config = self.config_curr.clone();
-            self.generated_passwords = self.password_maker.make_passwords();
+            match make_passwords(self.config_curr.clone()) {
+                Ok(passwords) => {
+                    self.generated_passwords = passwords;
+                    self.generate_error = None;
+                }
+                Err(e) => {
+                    self.generated_passwords.clear();
+                    self.generate_error = Some(e);
+                }
+            }
+        }
+        if let Some(e) = &self.generate_error {
+            ui.label(RichText::new(e.to_string()).color(Color32::RED));
         }
 
         for item in self.generated_passwords.iter() {
@@ -222,6 +418,27 @@ impl App {
     }
 }
 
+/// Make passwords with the RNG chosen in `config`.
+fn make_passwords(config: Config) -> Result<Vec<String>, ValidationError> {
+    fn make<T: PasswordRng>(rng: T, config: Config) -> Result<Vec<String>, ValidationError> {
+        PasswordMaker::from_rng(rng, config, default_wordlist()).try_make_passwords()
+    }
+    match config.rng_type {
+        RngType::OsRng => make(OsRng, config),
+        RngType::Csprng => make(ThreadRng::default(), config),
+        #[cfg(feature = "chacha")]
+        RngType::Chacha8 => make(ChaCha8Rng::from_os_rng(), config),
+        #[cfg(feature = "chacha")]
+        RngType::Chacha12 => make(ChaCha12Rng::from_os_rng(), config),
+        #[cfg(feature = "chacha")]
+        RngType::Chacha20 => make(ChaCha20Rng::from_os_rng(), config),
+        #[cfg(feature = "dice")]
+        RngType::Dice => Err(ValidationError::Message(
+            "dice rolls cannot be entered in the GUI, choose another rng type".to_owned(),
+        )),
+    }
+}
+
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -1,6 +1,16 @@
 pub mod config;
+pub mod config_file;
 pub mod consts;
+pub mod entropy;
+pub mod output;
 pub mod password_maker;
+pub mod policy;
+pub mod preset;
+pub mod rng;
+#[cfg(feature = "site")]
+pub mod site;
+pub mod template;
 pub mod test_helpers;
 pub mod types;
 pub mod word_transformer;
+pub mod wordlist;
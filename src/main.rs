@@ -19,18 +19,239 @@
 //!
 //! Use custom configurations, or roll with the defaults.
 use std::env;
+#[cfg(feature = "site")]
+use std::io;
+#[cfg(feature = "site")]
+use std::io::IsTerminal;
 use std::process::ExitCode;
 
+#[cfg(feature = "chacha")]
+use getopts::Matches;
 use getopts::Options;
+#[cfg(feature = "chacha")]
+use rand::SeedableRng;
 use rand::rngs::OsRng;
 use rand::rngs::ThreadRng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha20Rng;
 
+use fmn_passgen::config::Config;
 use fmn_passgen::config::ConfigBuilder;
+use fmn_passgen::config_file::from_env;
+use fmn_passgen::config_file::load_default_config;
+use fmn_passgen::config_file::read_config_file;
 use fmn_passgen::consts::default;
+use fmn_passgen::output::format_passwords;
+use fmn_passgen::password_maker::GeneratedPassword;
 use fmn_passgen::password_maker::PasswordMaker;
+use fmn_passgen::password_maker::default_wordlist;
+use fmn_passgen::preset::PresetStore;
+#[cfg(feature = "dice")]
+use fmn_passgen::rng::DiceRng;
+use fmn_passgen::rng::PasswordRng;
+use fmn_passgen::template;
+use fmn_passgen::types::CharacterClass;
+use fmn_passgen::types::GroupPlacement;
+use fmn_passgen::types::OutputFormat;
+use fmn_passgen::types::PaddingMode;
 use fmn_passgen::types::PaddingType;
+use fmn_passgen::types::Preset;
 use fmn_passgen::types::RngType;
+use fmn_passgen::types::SeparatorType;
+use fmn_passgen::types::StrEnum;
+use fmn_passgen::types::UppercaseLimitType;
+use fmn_passgen::types::ValidationError;
 use fmn_passgen::types::WordTransformationType;
+use fmn_passgen::wordlist::read_wordlist;
+
+/// Create passwords, using the default wordlist unless a custom one was loaded.
+///
+/// Also returns the config that was used, after applying any minimum entropy.
+fn make_passwords<T>(
+    rng: T,
+    config: Config,
+    wordlist: Option<Vec<String>>,
+) -> Result<(Config, Vec<GeneratedPassword>), ValidationError>
+where
+    T: PasswordRng,
+{
+    let wordlist = wordlist.unwrap_or_else(default_wordlist);
+    generate_passwords(PasswordMaker::from_rng(rng, config, wordlist))
+}
+
+/// Create passwords with a [`PasswordMaker`] that has already been set up.
+///
+/// Also returns the config that was used, after applying any minimum entropy.
+/// It is an error if the policy cannot be met.
+fn generate_passwords<T>(
+    mut maker: PasswordMaker<T>,
+) -> Result<(Config, Vec<GeneratedPassword>), ValidationError>
+where
+    T: PasswordRng,
+{
+    maker.resolve_min_entropy()?;
+    let passwords = maker.generate_passwords()?;
+    Ok((maker.config, passwords))
+}
+
+/// Read the seed for deterministic passwords, which must be confirmed with `--insecure-deterministic`.
+#[cfg(feature = "chacha")]
+fn parse_seed(matches: &Matches) -> Result<Option<u64>, ValidationError> {
+    let confirmed = matches.opt_present("insecure-deterministic");
+    match matches.opt_str("seed") {
+        None if confirmed => Err(ValidationError::Message(
+            "--insecure-deterministic does nothing without --seed".to_owned(),
+        )),
+        None => Ok(None),
+        Some(_) if !confirmed => Err(ValidationError::Message(
+            "anyone who knows the seed can recreate passwords made with --seed, \
+             add --insecure-deterministic if that is really what you want"
+                .to_owned(),
+        )),
+        Some(seed) => seed.parse().map(Some).map_err(|_| {
+            ValidationError::Message(format!(
+                "`{seed}` must be an integer between 0 and {}",
+                u64::MAX
+            ))
+        }),
+    }
+}
+
+/// Read the site name and counter for site passwords.
+#[cfg(feature = "site")]
+fn parse_site(matches: &Matches) -> Result<Option<(String, u32)>, ValidationError> {
+    let counter = matches.opt_str("counter");
+    let Some(site) = matches.opt_str("site") else {
+        return match counter {
+            Some(_) => Err(ValidationError::Message(
+                "--counter does nothing without --site".to_owned(),
+            )),
+            None => Ok(None),
+        };
+    };
+    if matches.opt_present("seed") {
+        return Err(ValidationError::Message(
+            "--site and --seed cannot be used together".to_owned(),
+        ));
+    }
+    let counter = match counter {
+        Some(counter) => counter.parse().map_err(|_| {
+            ValidationError::Message(format!(
+                "`{counter}` must be an integer between 0 and {}",
+                u32::MAX
+            ))
+        })?,
+        None => 1,
+    };
+    Ok(Some((site, counter)))
+}
+
+/// Ask for the master passphrase without echoing it,
+/// or read it from the first line of stdin if stdin is not a terminal.
+#[cfg(feature = "site")]
+fn read_master_passphrase() -> Result<String, ValidationError> {
+    let io_error = |e: io::Error| {
+        ValidationError::Message(format!("unable to read the master passphrase: {e}"))
+    };
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        rpassword::prompt_password("master passphrase: ").map_err(io_error)
+    } else {
+        let mut line = String::new();
+        stdin.read_line(&mut line).map_err(io_error)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+    }
+}
+
+/// Print the tokens that a template may use, in the same layout as [`print_choices`].
+fn print_template_tokens() {
+    println!("\nTEMPLATE TOKENS:");
+    let width = template::TOKENS
+        .iter()
+        .map(|(token, _)| token.len())
+        .max()
+        .unwrap_or(0);
+    for (token, description) in template::TOKENS {
+        println!("    {token:width$} ({description})");
+    }
+    println!(
+        "    {:width$} (a literal brace, anything else is copied as is)",
+        "{{ }}"
+    );
+}
+
+/// Print a help section listing every member of a [`StrEnum`] along with its description.
+fn print_choices<T: StrEnum>(heading: &str) {
+    println!("\n{heading}:");
+    let width = T::into_iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, member) in T::into_iter() {
+        let description = member.description();
+        if description.is_empty() {
+            println!("    {name}");
+        } else {
+            println!("    {name:width$} ({description})");
+        }
+    }
+}
+
+/// Find a built-in or stored preset by name.
+///
+/// Built-in presets work even when there is no config directory to store presets in.
+fn resolve_preset(name: &str) -> Result<ConfigBuilder, ValidationError> {
+    match PresetStore::open_default() {
+        Ok(store) => store.resolve(name),
+        Err(e) => Preset::to_member(&name.to_ascii_lowercase())
+            .map(|preset| preset.config_builder())
+            .map_err(|_| e),
+    }
+}
+
+/// Print every preset along with a sample password and its estimated entropy.
+fn list_presets(wordlist: Option<Vec<String>>) -> Result<(), ValidationError> {
+    let mut presets: Vec<(String, Result<ConfigBuilder, ValidationError>)> = Preset::into_iter()
+        .map(|(name, preset)| ((*name).to_owned(), Ok(preset.config_builder())))
+        .collect();
+    if let Ok(store) = PresetStore::open_default() {
+        for name in store.list()? {
+            let builder = store.load(&name);
+            presets.push((name, builder));
+        }
+    }
+    let width = presets
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    // a broken preset is reported without hiding the others
+    for (name, builder) in presets {
+        match builder.and_then(|builder| preview_preset(builder, wordlist.as_ref())) {
+            Ok((entropy, password)) => println!("{name:width$}  {entropy:>10}  {password}"),
+            Err(e) => eprintln!("{name:width$}  {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// The entropy of a preset and a sample password made with it.
+fn preview_preset(
+    builder: ConfigBuilder,
+    wordlist: Option<&Vec<String>>,
+) -> Result<(String, String), ValidationError> {
+    let config = builder.build()?;
+    let mut maker = match wordlist {
+        Some(wordlist) => PasswordMaker::<OsRng>::with_wordlist(config, wordlist.clone()),
+        None => PasswordMaker::<OsRng>::new(config),
+    };
+    let entropy = maker.entropy()?.to_string();
+    Ok((entropy, maker.try_make_password()?))
+}
 
 /// The entrypoint.
 ///
@@ -81,8 +302,33 @@ fn main() -> ExitCode {
     opts.optopt(
         "W",
         "word-transformation",
-        "transformation to apply to the selected words",
-        &format!("TYPE, default={}", &WordTransformationType::default()),
+        "transformations to apply to the selected words in order, comma separated",
+        &format!("TYPES, default={}", &WordTransformationType::default()),
+    );
+    opts.optopt(
+        "",
+        "leet-table",
+        "characters and their leet substitutes, comma separated",
+        &format!(
+            "TABLE, default={}",
+            default::LEET_TABLE
+                .iter()
+                .map(|(from, to)| format!("{from}{to}"))
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+    );
+    opts.optopt(
+        "",
+        "uppercase-limit",
+        "number of letters random-case uppercases, 0 to flip a coin for each",
+        &format!("NUM, default={}", default::UPPERCASE_LIMIT),
+    );
+    opts.optopt(
+        "",
+        "uppercase-limit-type",
+        "whether the uppercase-limit is per word or per password",
+        &format!("TYPE, default={}", &UppercaseLimitType::default()),
     );
     opts.optopt(
         "b",
@@ -96,6 +342,36 @@ fn main() -> ExitCode {
         "number of digits to append",
         &format!("NUM, default={}", default::DIGITS_AFTER),
     );
+    opts.optopt(
+        "g",
+        "group-placement",
+        "where to place groups of digits and symbols between words",
+        &format!("TYPE, default={}", &GroupPlacement::default()),
+    );
+    opts.optopt(
+        "",
+        "group-count",
+        "number of groups for random-gaps",
+        &format!("NUM, default={}", default::GROUP_COUNT),
+    );
+    opts.optopt(
+        "",
+        "group-digits",
+        "number of digits in each group",
+        &format!("NUM, default={}", default::GROUP_DIGITS),
+    );
+    opts.optopt(
+        "",
+        "group-symbols",
+        "number of symbols in each group",
+        &format!("NUM, default={}", default::GROUP_SYMBOLS),
+    );
+    opts.optopt(
+        "",
+        "symbols",
+        "list of characters to choose group symbols from",
+        &default_symbol_alphabet_help,
+    );
     opts.optopt(
         "T",
         "padding-type",
@@ -107,9 +383,10 @@ fn main() -> ExitCode {
         "padding-length",
         "how much to pad",
         &format!(
-            "NUM, default={} for fixed, {} for adaptive",
+            "NUM, default={} for fixed, {} for adaptive, {} for symmetric",
             default::PADDING_LENGTH_FIXED,
-            default::PADDING_LENGTH_ADAPTIVE
+            default::PADDING_LENGTH_ADAPTIVE,
+            default::PADDING_LENGTH_SYMMETRIC
         ),
     );
     opts.optopt(
@@ -118,18 +395,140 @@ fn main() -> ExitCode {
         "list of characters to choose from",
         &default_symbol_alphabet_help,
     );
+    opts.optopt(
+        "",
+        "padding-mode",
+        "how to choose the padding characters",
+        &format!("MODE, default={}", &PaddingMode::default()),
+    );
     opts.optopt(
         "s",
         "separators",
         "list of characters to choose from",
         &default_symbol_alphabet_help,
     );
+    opts.optopt(
+        "S",
+        "separator-type",
+        "how to choose the separators",
+        &format!("TYPE, default={}", &SeparatorType::default()),
+    );
+    opts.optopt(
+        "",
+        "separator-tokens",
+        "list of tokens to choose from, comma separated",
+        &format!(
+            "TOKENS, default=\"{}\"",
+            default::SEPARATOR_TOKENS.join(",")
+        ),
+    );
+    opts.optopt(
+        "t",
+        "template",
+        "the shape of every password, which replaces the word count, digits, groups and padding",
+        "TEMPLATE",
+    );
     opts.optopt(
         "r",
         "rng",
         "method of random number generation",
         &format!("TYPE, default={}", &RngType::default()),
     );
+    #[cfg(feature = "chacha")]
+    opts.optopt(
+        "",
+        "seed",
+        "INSECURE: make the same passwords every time with a seeded ChaCha20, for testing",
+        "N",
+    );
+    #[cfg(feature = "chacha")]
+    opts.optflag(
+        "",
+        "insecure-deterministic",
+        "required by --seed, to confirm that predictable passwords are wanted",
+    );
+    #[cfg(feature = "site")]
+    opts.optopt(
+        "",
+        "site",
+        "derive passwords for a site from a master passphrase, which is asked for",
+        "NAME",
+    );
+    #[cfg(feature = "site")]
+    opts.optopt(
+        "",
+        "counter",
+        "change this to change a site's password",
+        "N, default=1",
+    );
+    opts.optopt(
+        "",
+        "min-entropy",
+        "choose the fewest words that give at least this much entropy, overrides word-count",
+        "BITS",
+    );
+    opts.optopt(
+        "",
+        "max-length",
+        "the most characters a password may have, words are chosen to fit",
+        "NUM",
+    );
+    opts.optopt(
+        "",
+        "require",
+        "character classes that must each appear, comma separated",
+        "CLASSES",
+    );
+    opts.optopt(
+        "",
+        "max-repeated",
+        "the most times a character may appear in a row",
+        "NUM",
+    );
+    opts.optopt("", "ban", "characters that must not appear", "CHARS");
+    opts.optflag("e", "entropy", "show the entropy of each password");
+    opts.optopt(
+        "f",
+        "format",
+        "how to print passwords, structured formats include entropy and settings",
+        &format!("FORMAT, default={}", &OutputFormat::default()),
+    );
+    opts.optopt(
+        "P",
+        "preset",
+        "start from a preset, individual options override it",
+        &format!("NAME, default={}", &Preset::default()),
+    );
+    opts.optflag(
+        "",
+        "list-presets",
+        "show every preset with a sample password",
+    );
+    opts.optopt(
+        "",
+        "save-preset",
+        "save the resulting settings as a preset instead of making passwords",
+        "NAME",
+    );
+    opts.optopt(
+        "",
+        "show-preset",
+        "print the settings of a saved preset",
+        "NAME",
+    );
+    opts.optopt("", "delete-preset", "delete a saved preset", "NAME");
+    opts.optopt(
+        "",
+        "config",
+        "file to load settings from",
+        "PATH, default=$XDG_CONFIG_HOME/fmn-passgen/config.toml",
+    );
+    opts.optopt(
+        "",
+        "wordlist",
+        "newline delimited file of words to choose from",
+        "PATH, default=EFF large wordlist",
+    );
 
     let matches = match opts.parse(&args[1..]) {
         Ok(v) => v,
@@ -144,56 +543,204 @@ fn main() -> ExitCode {
         let brief = format!("Usage: {program_name} [options]");
         println!("{}", opts.usage(&brief));
         println!("types are case insensitive");
-        // TODO make less brittle, see crate::types
-        println!("\nWORD TRANSFORMATIONS:");
-        println!("    none");
-        println!("    lower                   (correct horse battery staple)");
-        println!("    upper                   (CORRECT HORSE BATTERY STAPLE)");
-        println!("    capitalize-first        (Correct Horse Battery Staple)");
-        println!("    capitalize-last         (correcT horsE batterY staplE)");
-        println!("    capitalize-not-first    (cORRECT hORSE bATTERY sTAPLE)");
-        println!("    alternating-lower-upper (correct HORSE battery STAPLE)");
-        println!("    alternating-upper-lower (CORRECT horse BATTERY staple)");
-        println!("    random-upper-lower      (correct HORSE battery staple)");
-        println!("\nPADDING TYPES:");
-        println!("    none");
-        println!("    fixed    (add padding-length padding-characters to front and back)");
-        #[rustfmt::skip]
-        println!("    adaptive (if unpadded password is less than padding-length, append padding-characters to meet length)");
-        println!("\nRNG TYPES:");
-        println!("    os-rng (the system's native secure RNG)");
-        println!("    csprng (a reasonably secure userspace RNG)");
+        print_choices::<WordTransformationType>("WORD TRANSFORMATIONS");
+        print_choices::<UppercaseLimitType>("UPPERCASE LIMIT TYPES");
+        print_choices::<GroupPlacement>("GROUP PLACEMENTS");
+        print_choices::<PaddingType>("PADDING TYPES");
+        print_choices::<PaddingMode>("PADDING MODES");
+        print_choices::<SeparatorType>("SEPARATOR TYPES");
+        print_choices::<RngType>("RNG TYPES");
+        print_choices::<CharacterClass>("CHARACTER CLASSES");
+        print_template_tokens();
+        print_choices::<Preset>("PRESETS");
+        print_choices::<OutputFormat>("OUTPUT FORMATS");
         return ExitCode::SUCCESS;
     }
 
+    // settings are layered, from lowest to highest precedence:
+    // defaults < config file < environment < preset < command line
+    let config_file = match matches.opt_str("config") {
+        Some(path) => read_config_file(path),
+        None => load_default_config(),
+    };
+    let config_builder = match config_file.and_then(|file| {
+        let mut builder = file.merge(from_env()?);
+        if let Some(name) = matches.opt_str("preset") {
+            builder = builder.merge(resolve_preset(&name)?);
+        }
+        Ok(builder)
+    }) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
     // TODO this boilerplate could be reduced
-    let config_builder = ConfigBuilder::new()
+    let cli_config_builder = ConfigBuilder::new()
         .count(matches.opt_str("count"))
         .word_count(matches.opt_str("word-count"))
         .word_min_length(matches.opt_str("word-min-length"))
         .word_max_length(matches.opt_str("word-max-length"))
         .word_transformation(matches.opt_str("word-transformation"))
+        .leet_table(matches.opt_str("leet-table"))
+        .uppercase_limit(matches.opt_str("uppercase-limit"))
+        .uppercase_limit_type(matches.opt_str("uppercase-limit-type"))
         .digits_before(matches.opt_str("digits-before"))
         .digits_after(matches.opt_str("digits-after"))
+        .group_placement(matches.opt_str("group-placement"))
+        .group_count(matches.opt_str("group-count"))
+        .group_digits(matches.opt_str("group-digits"))
+        .group_symbols(matches.opt_str("group-symbols"))
+        .symbol_characters(matches.opt_str("symbols"))
         .padding_type(matches.opt_str("padding-type"))
         .padding_length(matches.opt_str("padding-length"))
         .padding_characters(matches.opt_str("padding-characters"))
+        .padding_mode(matches.opt_str("padding-mode"))
         .separator_characters(matches.opt_str("separators"))
-        .rng_type(matches.opt_str("rng"));
+        .separator_type(matches.opt_str("separator-type"))
+        .separator_tokens(matches.opt_str("separator-tokens"))
+        .rng_type(matches.opt_str("rng"))
+        .min_entropy(matches.opt_str("min-entropy"))
+        .max_length(matches.opt_str("max-length"))
+        .required_characters(matches.opt_str("require"))
+        .max_repeated(matches.opt_str("max-repeated"))
+        .banned_characters(matches.opt_str("ban"))
+        .template(matches.opt_str("template"));
+    let config_builder = config_builder.merge(cli_config_builder);
 
-    match config_builder.build() {
+    let wordlist = match matches.opt_str("wordlist").map(read_wordlist).transpose() {
+        Ok(v) => v.map(|wordlist| wordlist.words),
         Err(e) => {
             eprintln!("{e}");
-            ExitCode::FAILURE
+            return ExitCode::FAILURE;
         }
-        Ok(config) => {
-            let result = match config.rng_type {
-                RngType::OsRng => PasswordMaker::<OsRng>::new(config).make_passwords(),
-                RngType::Csprng => PasswordMaker::<ThreadRng>::new(config).make_passwords(),
-            };
-            for password in result {
-                println!("{password}");
+    };
+
+    if let Some(name) = matches.opt_str("show-preset") {
+        return match PresetStore::open_default().and_then(|store| store.show(&name)) {
+            Ok(contents) => {
+                print!("{contents}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if let Some(name) = matches.opt_str("delete-preset") {
+        return match PresetStore::open_default().and_then(|store| store.delete(&name)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
             }
+        };
+    }
+    if let Some(name) = matches.opt_str("save-preset") {
+        let result = config_builder.build().and_then(|config| {
+            PresetStore::open_default().and_then(|store| store.save(&name, &config))
+        });
+        return match result {
+            Ok(path) => {
+                println!("saved preset `{name}` to {}", path.display());
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if matches.opt_present("list-presets") {
+        return match list_presets(wordlist) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let format = match matches
+        .opt_str("format")
+        .map(|name| OutputFormat::to_member(&name.to_ascii_lowercase()).copied())
+        .transpose()
+    {
+        Ok(v) => v.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    #[cfg(feature = "chacha")]
+    let seed = match parse_seed(&matches) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    #[cfg(feature = "site")]
+    let site = match parse_site(&matches) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = config_builder.build().and_then(|config| {
+        #[cfg(feature = "site")]
+        if let Some((site, counter)) = site {
+            let master = read_master_passphrase()?;
+            let config = Config {
+                rng_type: RngType::Chacha20,
+                ..config
+            };
+            let wordlist = wordlist.unwrap_or_else(default_wordlist);
+            return generate_passwords(PasswordMaker::for_site(
+                config, wordlist, &master, &site, counter,
+            )?);
+        }
+        #[cfg(feature = "chacha")]
+        if let Some(seed) = seed {
+            eprintln!("warning: passwords made with --seed are predictable, do not use them");
+            let config = Config {
+                rng_type: RngType::Chacha20,
+                ..config
+            };
+            return make_passwords(ChaCha20Rng::seed_from_u64(seed), config, wordlist);
+        }
+        match config.rng_type {
+            RngType::OsRng => make_passwords(OsRng, config, wordlist),
+            RngType::Csprng => make_passwords(ThreadRng::default(), config, wordlist),
+            #[cfg(feature = "chacha")]
+            RngType::Chacha8 => make_passwords(ChaCha8Rng::from_os_rng(), config, wordlist),
+            #[cfg(feature = "chacha")]
+            RngType::Chacha12 => make_passwords(ChaCha12Rng::from_os_rng(), config, wordlist),
+            #[cfg(feature = "chacha")]
+            RngType::Chacha20 => make_passwords(ChaCha20Rng::from_os_rng(), config, wordlist),
+            #[cfg(feature = "dice")]
+            RngType::Dice => make_passwords(DiceRng::default(), config, wordlist),
+        }
+    });
+
+    match result {
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+        Ok((config, passwords)) => {
+            let show_entropy = matches.opt_present("entropy");
+            print!(
+                "{}",
+                format_passwords(format, &config, &passwords, show_entropy)
+            );
             ExitCode::SUCCESS
         }
     }
@@ -0,0 +1,315 @@
+//! Print passwords in the formats described by [`OutputFormat`].
+//!
+//! The structured formats describe each password with its length, entropy, the words and
+//! characters chosen for it, and the [`Config`] it was made with.
+//! Values are escaped by hand to avoid pulling in serialization dependencies.
+use std::fmt::Write;
+
+use crate::config::Config;
+use crate::config::ConfigBuilder;
+use crate::password_maker::GeneratedPassword;
+use crate::types::OutputFormat;
+
+/// The names of the columns that come before the config values in CSV and TSV output.
+///
+/// `separators` and `groups` hold one entry per gap, written as a JSON array of strings
+/// since the entries may contain spaces or be empty.
+const COLUMNS: [&str; 8] = [
+    "password",
+    "length",
+    "entropy",
+    "words",
+    "separator",
+    "padding_character",
+    "separators",
+    "groups",
+];
+
+/// Format `passwords`, which were made with `config`, ending each record with a newline.
+///
+/// `show_entropy` only affects [`OutputFormat::Plain`],
+/// the structured formats always include the entropy.
+pub fn format_passwords(
+    format: OutputFormat,
+    config: &Config,
+    passwords: &[GeneratedPassword],
+    show_entropy: bool,
+) -> String {
+    let config = ConfigBuilder::from(config);
+    let mut buf = String::new();
+    match format {
+        OutputFormat::Plain => {
+            for generated in passwords {
+                if show_entropy {
+                    let _ = writeln!(buf, "{generated}\t{}", generated.entropy);
+                } else {
+                    let _ = writeln!(buf, "{generated}");
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let objects: Vec<String> = passwords
+                .iter()
+                .map(|generated| format!("  {}", json_object(&config, generated)))
+                .collect();
+            if objects.is_empty() {
+                buf.push_str("[]\n");
+            } else {
+                let _ = writeln!(buf, "[\n{}\n]", objects.join(",\n"));
+            }
+        }
+        OutputFormat::Jsonl => {
+            for generated in passwords {
+                let _ = writeln!(buf, "{}", json_object(&config, generated));
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let (delimiter, escape): (&str, fn(&str) -> String) = match format {
+                OutputFormat::Csv => (",", csv_field),
+                _ => ("\t", tsv_field),
+            };
+            let header: Vec<&str> = COLUMNS
+                .iter()
+                .chain(ConfigBuilder::FIELD_NAMES)
+                .copied()
+                .collect();
+            let _ = writeln!(buf, "{}", header.join(delimiter));
+            for generated in passwords {
+                let mut row = vec![
+                    generated.to_string(),
+                    generated.len().to_string(),
+                    generated.entropy.total().to_string(),
+                    generated.words.join(" "),
+                    generated.separator().map(String::from).unwrap_or_default(),
+                    generated
+                        .padding_character
+                        .map(String::from)
+                        .unwrap_or_default(),
+                    json_array(&generated.separators),
+                    json_array(&generated.groups),
+                ];
+                row.extend(config_values(&config).map(|(_, value)| value.to_owned()));
+                let row: Vec<String> = row.iter().map(|field| escape(field)).collect();
+                let _ = writeln!(buf, "{}", row.join(delimiter));
+            }
+        }
+    }
+    buf
+}
+
+/// Every config value, by name.
+fn config_values(config: &ConfigBuilder) -> impl Iterator<Item = (&'static str, &str)> {
+    ConfigBuilder::FIELD_NAMES
+        .iter()
+        .map(|name| (*name, config.field(name).and_then(|v| v.as_deref())))
+        .map(|(name, value)| (name, value.unwrap_or_default()))
+}
+
+/// Describe a password as a single line JSON object.
+fn json_object(config: &ConfigBuilder, generated: &GeneratedPassword) -> String {
+    let optional_char =
+        |c: Option<char>| c.map_or_else(|| "null".to_owned(), |c| json_string(&String::from(c)));
+    let config: Vec<String> = config_values(config)
+        .map(|(name, value)| {
+            let number = ConfigBuilder::INTEGER_FIELDS
+                .contains(&name)
+                .then(|| value.parse::<u8>().ok())
+                .flatten();
+            let value = number.map_or_else(|| json_string(value), |n| n.to_string());
+            format!("{}:{value}", json_string(name))
+        })
+        .collect();
+    format!(
+        "{{\"password\":{},\"length\":{},\"entropy\":{},\"words\":{},\"separator\":{},\"padding_character\":{},\"separators\":{},\"groups\":{},\"config\":{{{}}}}}",
+        json_string(&generated.to_string()),
+        generated.len(),
+        generated.entropy.total(),
+        json_array(&generated.words),
+        optional_char(generated.separator()),
+        optional_char(generated.padding_character),
+        json_array(&generated.separators),
+        json_array(&generated.groups),
+        config.join(","),
+    )
+}
+
+/// Write `values` as a JSON array of strings.
+fn json_array(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| json_string(value)).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// Quote and escape a JSON string.
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(result, "\\u{:04x}", u32::from(c));
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break, as in RFC 4180.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Escape tabs, line breaks and backslashes in a TSV field.
+fn tsv_field(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use crate::types::GroupPlacement;
+    use crate::types::SeparatorType;
+
+    /// Make two passwords with an awkward separator.
+    fn make_passwords() -> (Config, Vec<GeneratedPassword>) {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.count = 2;
+        maker.config.separator_characters = vec![','];
+        maker.config.padding_characters = vec!['"'];
+        let passwords = maker.generate_passwords().unwrap();
+        (maker.config, passwords)
+    }
+
+    #[test]
+    fn test_plain() {
+        let (config, passwords) = make_passwords();
+        let output = format_passwords(OutputFormat::Plain, &config, &passwords, false);
+        assert_eq!(output.lines().count(), 2);
+        assert_eq!(output.lines().next().unwrap(), passwords[0].to_string());
+        let output = format_passwords(OutputFormat::Plain, &config, &passwords, true);
+        let expected = format!("{}\t{}", passwords[0], passwords[0].entropy);
+        assert_eq!(output.lines().next().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_jsonl() {
+        let (config, passwords) = make_passwords();
+        let output = format_passwords(OutputFormat::Jsonl, &config, &passwords, false);
+        let line = output.lines().next().unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(line.starts_with(
+            "{\"password\":\"\\\"startling,SHAFT,cactus,SHACK,15\\\"\",\"length\":33,"
+        ));
+        assert!(line.contains(
+            "\"words\":[\"startling\",\"SHAFT\",\"cactus\",\"SHACK\"],\"separator\":\",\",\"padding_character\":\"\\\"\","
+        ));
+        assert!(line.contains(
+            "\"separators\":[\",\",\",\",\",\",\",\"],\"groups\":[\"\",\"\",\"\"],\"config\""
+        ));
+        assert!(line.contains("\"config\":{\"count\":2,\"word_count\":4,"));
+        assert!(line.contains("\"rng_type\":\"os-rng\",\"min_entropy\":0,"));
+        assert!(line.ends_with("\"banned_characters\":\"\",\"template\":\"\"}}"));
+    }
+
+    #[test]
+    fn test_jsonl_separators_and_groups() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.separator_type = SeparatorType::Tokens;
+        maker.config.separator_tokens = vec![" and ".to_owned()];
+        maker.config.group_placement = GroupPlacement::EveryGap;
+        maker.config.group_digits = 1;
+        maker.config.group_symbols = 0;
+        let passwords = maker.generate_passwords().unwrap();
+        let generated = &passwords[0];
+        // no one separator character was used, but every separator is still there
+        assert_eq!(generated.separator(), None);
+        let output = format_passwords(OutputFormat::Jsonl, &maker.config, &passwords, false);
+        assert!(output.contains("\"separator\":null,"));
+        assert!(output.contains(&format!(
+            "\"separators\":{},\"groups\":{},",
+            json_array(&generated.separators),
+            json_array(&generated.groups)
+        )));
+        assert!(generated.groups.iter().all(|group| group.len() == 1));
+        let output = format_passwords(OutputFormat::Csv, &maker.config, &passwords, false);
+        let row = output.lines().nth(1).unwrap();
+        assert!(row.contains(&csv_field(&json_array(&generated.separators))));
+        assert!(row.contains(&csv_field(&json_array(&generated.groups))));
+    }
+
+    #[test]
+    fn test_jsonl_quotes_digit_characters() {
+        let (mut config, passwords) = make_passwords();
+        config.padding_characters = vec!['0', '1'];
+        config.separator_characters = vec!['7'];
+        let output = format_passwords(OutputFormat::Jsonl, &config, &passwords, false);
+        assert!(output.contains("\"padding_characters\":\"01\","));
+        assert!(output.contains("\"separator_characters\":\"7\","));
+        assert!(output.contains("\"padding_length\":1,"));
+    }
+
+    #[test]
+    fn test_json() {
+        let (config, passwords) = make_passwords();
+        let output = format_passwords(OutputFormat::Json, &config, &passwords, false);
+        let jsonl = format_passwords(OutputFormat::Jsonl, &config, &passwords, false);
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(output, format!("[\n  {},\n  {}\n]\n", lines[0], lines[1]));
+        assert_eq!(
+            format_passwords(OutputFormat::Json, &config, &[], false),
+            "[]\n"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let (config, passwords) = make_passwords();
+        let output = format_passwords(OutputFormat::Csv, &config, &passwords, false);
+        let mut lines = output.lines();
+        assert!(lines.next().unwrap().starts_with(
+            "password,length,entropy,words,separator,padding_character,separators,groups,count,"
+        ));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("\"\"\"startling,SHAFT,cactus,SHACK,15\"\"\",33,"));
+        assert!(row.contains(",startling SHAFT cactus SHACK,\",\",\"\"\"\",\"[\"\",\"\",\"\",\"\",\"\",\"\",\"\",\"\"]\",\"[\"\"\"\",\"\"\"\",\"\"\"\"]\",2,4,"));
+    }
+
+    #[test]
+    fn test_tsv() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.separator_characters = vec!['\t'];
+        let passwords = maker.generate_passwords().unwrap();
+        let output = format_passwords(OutputFormat::Tsv, &maker.config, &passwords, false);
+        let mut lines = output.lines();
+        assert_eq!(lines.next().unwrap().split('\t').count(), 37);
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("+startling\\tSHAFT\\tcactus\\tSHACK\\t15+\t33\t"));
+        assert_eq!(row.split('\t').count(), 37);
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\u{1}\n"), "\"a\\\"b\\\\c\\u0001\\n\"");
+    }
+}
@@ -5,33 +5,207 @@
 // static WORDLIST: &[&str] = &[...]
 include!(concat!(env!("OUT_DIR"), "/wordlist.rs"));
 
+use std::fmt;
 use std::iter;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
 
-use rand::TryRngCore;
-use rand::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
 use rand_core::UnwrapErr;
 
 use crate::config::Config;
 use crate::config::ConfigBuilder;
 use crate::consts::DIGIT_ALPHABET;
+use crate::entropy;
+use crate::entropy::Entropy;
+use crate::policy;
+use crate::rng;
+use crate::rng::PasswordRng;
+use crate::template::Template;
+use crate::template::Token;
+use crate::types::GroupPlacement;
+use crate::types::PaddingMode;
 use crate::types::PaddingType;
-use crate::types::WordTransformationType;
+use crate::types::SeparatorType;
+use crate::types::ValidationError;
 use crate::word_transformer;
+use crate::word_transformer::WordTransformer;
+use crate::wordlist;
+use crate::wordlist::WordlistError;
+
+/// A password along with the parts it was made from.
+///
+/// The password itself is produced by the [`fmt::Display`] impl.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedPassword {
+    /// the words chosen from the wordlist, before transformation
+    pub chosen_words: Vec<String>,
+    /// the words chosen from the wordlist, after transformation
+    pub words: Vec<String>,
+    /// the digits placed before the words, possibly empty
+    pub front_digits: String,
+    /// the digits placed after the words, possibly empty
+    pub back_digits: String,
+    /// the group of digits and symbols placed in each gap between words, empty where there is none
+    pub groups: Vec<String>,
+    /// the separators placed in each gap between the words and pseudo-words, in order
+    pub separators: Vec<String>,
+    /// the character the password was padded with, if it was padded with copies of one character
+    pub padding_character: Option<char>,
+    /// the padding placed before the words and pseudo-words, possibly empty
+    pub front_padding: String,
+    /// the padding placed after the words and pseudo-words, possibly empty
+    pub back_padding: String,
+    /// how the parts are put together into the password
+    pub layout: Layout,
+    /// how many bits of entropy went into making the password
+    pub entropy: Entropy,
+}
+
+/// How the parts of a [`GeneratedPassword`] are put together.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// the digits, words and groups, joined by the separators and surrounded by the padding
+    #[default]
+    Words,
+    /// the text made from each token of a [`Template`], in order,
+    /// which places its own words, separators and padding
+    Template(Vec<String>),
+}
+
+impl GeneratedPassword {
+    /// The non-empty words and pseudo-words, in order.
+    ///
+    /// For a template, these are the non-empty pieces made from its tokens,
+    /// separators and padding included.
+    pub fn parts(&self) -> Vec<&str> {
+        if let Layout::Template(pieces) = &self.layout {
+            return pieces
+                .iter()
+                .map(String::as_str)
+                .filter(|piece| !piece.is_empty())
+                .collect();
+        }
+        let words = self.words.iter().enumerate().flat_map(|(i, word)| {
+            let group = i.checked_sub(1).and_then(|gap| self.groups.get(gap));
+            group
+                .into_iter()
+                .chain(iter::once(word))
+                .map(String::as_str)
+        });
+        iter::once(self.front_digits.as_str())
+            .chain(words)
+            .chain(iter::once(self.back_digits.as_str()))
+            .filter(|part| !part.is_empty())
+            .collect()
+    }
+    /// The password without any padding.
+    ///
+    /// Separators are only placed between non-empty parts.
+    /// A template places its own padding, which is included.
+    pub fn unpadded(&self) -> String {
+        if let Layout::Template(pieces) = &self.layout {
+            return pieces.concat();
+        }
+        let mut result = String::new();
+        for (i, part) in self.parts().into_iter().enumerate() {
+            if i > 0 {
+                result.push_str(self.separators.get(i - 1).map_or("", String::as_str));
+            }
+            result.push_str(part);
+        }
+        result
+    }
+    /// The length of the password in characters.
+    pub fn len(&self) -> usize {
+        self.unpadded().chars().count()
+            + self.front_padding.chars().count()
+            + self.back_padding.chars().count()
+    }
+    /// Whether the password is the empty string.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The character placed between words and pseudo-words, if the same one character was placed in every gap.
+    pub fn separator(&self) -> Option<char> {
+        let mut chars = self.separators.iter().map(|separator| {
+            let mut chars = separator.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        });
+        let first = chars.next().flatten();
+        first.filter(|first| chars.all(|c| c == Some(*first)))
+    }
+    /// How many padding characters come before the password.
+    pub fn front_padding_length(&self) -> usize {
+        self.front_padding.chars().count()
+    }
+    /// How many padding characters come after the password.
+    pub fn back_padding_length(&self) -> usize {
+        self.back_padding.chars().count()
+    }
+}
+
+impl fmt::Display for GeneratedPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.front_padding,
+            self.unpadded(),
+            self.back_padding
+        )
+    }
+}
+
+/// The EFF large wordlist, which is built into the binary.
+pub fn default_wordlist() -> Vec<String> {
+    WORDLIST.iter().map(|s| String::from(*s)).collect()
+}
 
 /// Turn a [`Config`] into passwords.
 #[derive(Debug)]
 pub struct PasswordMaker<T>
 where
-    T: TryRngCore,
+    T: PasswordRng,
 {
     pub rng: UnwrapErr<T>,
     pub config: Config,
     pub wordlist: Vec<String>,
+    /// applied to the words after [`Config::word_transformation`], see [`PasswordMaker::with_transformer`]
+    pub transformer: Option<Arc<dyn WordTransformer>>,
+    /// the last policy cost worked out, see [`PasswordMaker::policy_cost`]
+    policy_cost: Mutex<Option<PolicyCost>>,
+}
+
+/// The cost of the policy, along with the settings it was estimated for.
+///
+/// The settings are public fields of [`PasswordMaker`] which may change at any time,
+/// so they are compared before the cost is reused.
+#[derive(Debug)]
+struct PolicyCost {
+    config: Config,
+    wordlist: Vec<String>,
+    transformer: Option<Arc<dyn WordTransformer>>,
+    cost: Result<f64, ValidationError>,
+}
+
+impl PolicyCost {
+    /// Whether the cost was estimated for the current settings of `maker`.
+    fn is_for<T: PasswordRng>(&self, maker: &PasswordMaker<T>) -> bool {
+        let same_transformer = match (&self.transformer, &maker.transformer) {
+            (Some(ours), Some(theirs)) => Arc::ptr_eq(ours, theirs),
+            (ours, theirs) => ours.is_none() && theirs.is_none(),
+        };
+        same_transformer && self.config == maker.config && self.wordlist == maker.wordlist
+    }
 }
 
 impl<T> Default for PasswordMaker<T>
 where
-    T: TryRngCore + Default,
+    T: PasswordRng + Default,
 {
     fn default() -> Self {
         #[expect(
@@ -41,8 +215,10 @@ where
         let config = ConfigBuilder::new().build().unwrap();
         Self {
             rng: T::default().unwrap_err(),
-            wordlist: WORDLIST.iter().map(|s| String::from(*s)).collect(),
+            wordlist: default_wordlist(),
             config,
+            transformer: None,
+            policy_cost: Mutex::default(),
         }
     }
 }
@@ -52,37 +228,118 @@ where
 /// is not too bad, and `SeedableRng` is only useful for testing.
 impl<T> PasswordMaker<T>
 where
-    T: TryRngCore + Default,
+    T: PasswordRng + Default,
 {
     pub fn new(config: Config) -> Self {
-        Self {
-            rng: T::default().unwrap_err(),
+        Self::with_wordlist(config, default_wordlist())
+    }
+    /// Create a [`PasswordMaker`] that chooses words from a custom wordlist.
+    pub fn with_wordlist(config: Config, wordlist: Vec<String>) -> Self {
+        Self::from_rng(T::default(), config, wordlist)
+    }
+    /// Create a [`PasswordMaker`] that chooses words from a newline delimited wordlist file.
+    ///
+    /// See [`wordlist::parse_wordlist`] for the validation rules.
+    pub fn from_wordlist_file(
+        config: Config,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, WordlistError> {
+        Ok(Self::with_wordlist(
             config,
-            wordlist: WORDLIST.iter().map(|s| String::from(*s)).collect(),
-        }
+            wordlist::read_wordlist(path)?.words,
+        ))
     }
 }
 
 impl<T> PasswordMaker<T>
 where
-    T: TryRngCore,
+    T: PasswordRng,
 {
-    /// Filter out words that do not fit between the configured minimum and maximum length.
+    /// Create a [`PasswordMaker`] with an RNG that has already been set up,
+    /// such as one that does not impl [`Default`] because it must be seeded.
+    pub fn from_rng(rng: T, config: Config, wordlist: Vec<String>) -> Self {
+        Self {
+            rng: rng.unwrap_err(),
+            config,
+            wordlist,
+            transformer: None,
+            policy_cost: Mutex::default(),
+        }
+    }
+    /// Transform the chosen words with `transformer` as well,
+    /// after [`Config::word_transformation`], or for a template after each word's token.
+    ///
+    /// See [`WordTransformer`] for how this affects the entropy estimate.
+    #[must_use]
+    pub fn with_transformer(mut self, transformer: impl WordTransformer + 'static) -> Self {
+        self.transformer = Some(Arc::new(transformer));
+        self
+    }
+    /// Filter out words that do not fit between the configured minimum and maximum length,
+    /// or that contain characters banned by the policy.
     ///
     /// Return indexes indicating which words we wish to keep.
     /// Working with indexes avoids pointer hell and reduces memory allocation and storage requirements.
-    #[expect(
-        clippy::cast_possible_truncation,
-        reason = "u32 MAX is more than enough for any reasonable word list length"
-    )]
-    fn filter_wordlist(&self) -> Vec<u32> {
-        let min_len = self.config.word_min_length as usize;
-        let max_len = self.config.word_max_length as usize;
-        self.wordlist
+    pub fn filter_wordlist(&self) -> Vec<u32> {
+        filter_wordlist(&self.config, &self.wordlist)
+    }
+    /// Estimate the entropy of passwords made with the current [`Config`] and wordlist.
+    ///
+    /// See [`Entropy::estimate`] and [`PasswordMaker::policy_cost`].
+    /// It is an error if the policy cannot be met.
+    pub fn entropy(&self) -> Result<Entropy, ValidationError> {
+        let mut entropy = WordChoices::new(&self.config, &self.wordlist)?.estimate(&self.config);
+        entropy.policy = self.policy_cost()?;
+        Ok(entropy)
+    }
+    /// Estimate how many bits of entropy are lost by throwing away passwords that break the policy.
+    ///
+    /// See [`policy_cost`].
+    /// Sampling is slow, so the estimate is kept until the settings change.
+    pub fn policy_cost(&self) -> Result<f64, ValidationError> {
+        if self.config.policy.is_empty() {
+            return Ok(0.0);
+        }
+        let mut cached = self
+            .policy_cost
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(cached) = cached.as_ref()
+            && cached.is_for(self)
+        {
+            return cached.cost.clone();
+        }
+        let cost = sample_policy_cost(&self.config, &self.wordlist, self.transformer.as_ref());
+        *cached = Some(PolicyCost {
+            config: self.config.clone(),
+            wordlist: self.wordlist.clone(),
+            transformer: self.transformer.clone(),
+            cost: cost.clone(),
+        });
+        cost
+    }
+    /// Apply the configured minimum entropy, if any, by adjusting the word count.
+    ///
+    /// The cost of the policy is taken into account, see [`Config::resolve_min_entropy`].
+    pub fn resolve_min_entropy(&mut self) -> Result<(), ValidationError> {
+        let wordlist = &self.wordlist;
+        let transformer = self.transformer.as_ref();
+        self.config.resolve_min_entropy_with(|config| {
+            // a word count that can never meet the policy is no use at all
+            estimate_entropy(config, wordlist, transformer).map_or(0.0, |entropy| entropy.total())
+        })
+    }
+    /// Choose with replacement a word for each slot, from the words that fit in that slot.
+    ///
+    /// See [`Config::word_length_budgets`].
+    fn choose_budgeted_words(&mut self, slots: &[Vec<u32>]) -> Vec<String> {
+        slots
             .iter()
-            .enumerate()
-            .filter(|(_, word)| (min_len..=max_len).contains(&word.chars().count()))
-            .map(|(i, _)| i as u32)
+            .map(|slot| {
+                let i = rng::choose(&mut self.rng.0, slot)
+                    .expect("invariant: every slot has at least one word that fits");
+                self.wordlist[*i as usize].clone()
+            })
             .collect()
     }
     /// Choose with replacement a configured number of words.
@@ -95,39 +352,24 @@ where
         let n = self.config.word_count as usize;
         let mut buf = Vec::with_capacity(n);
         for _ in 0..n {
-            buf.push(indices.choose(&mut self.rng).expect(concat!(
-                "invariant 1: `indices` must not be empty and should have been guarded above.\n",
-                "invariant 2: size_hint on a slice iterator with no intermediary ",
-                "iterator adapters should always be accurate.",
-            )));
+            buf.push(rng::choose(&mut self.rng.0, indices).expect(
+                "invariant: `indices` must not be empty and should have been guarded above.",
+            ));
         }
         buf.into_iter()
             .map(|n| self.wordlist[*n as usize].clone())
             .collect()
     }
-    /// Use the configured [`WordTransformationType`] to transform a [`Vec<String>`] of words.
+    /// Transform a [`Vec<String>`] of words with [`Config::word_transformation`],
+    /// then with the custom [`WordTransformer`], if there is one.
     fn transform_words(&mut self, words: Vec<String>) -> Vec<String> {
         if words.is_empty() {
             return words;
         }
-        match self.config.word_transformation {
-            WordTransformationType::None => words,
-            WordTransformationType::Lower => word_transformer::lower(words),
-            WordTransformationType::Upper => word_transformer::upper(words),
-            WordTransformationType::CapitalizeFirst => word_transformer::capitalize_first(words),
-            WordTransformationType::CapitalizeLast => word_transformer::capitalize_last(words),
-            WordTransformationType::CapitalizeNotFirst => {
-                word_transformer::capitalize_not_first(words)
-            }
-            WordTransformationType::AlternatingLowerUpper => {
-                word_transformer::alternating_lower_upper(words)
-            }
-            WordTransformationType::AlternatingUpperLower => {
-                word_transformer::alternating_upper_lower(words)
-            }
-            WordTransformationType::RandomUpperLower => {
-                word_transformer::random_upper_lower(&mut self.rng, words)
-            }
+        let words = self.config.transform(&mut self.rng.0, words);
+        match &self.transformer {
+            Some(transformer) => transformer.transform(&mut self.rng.0, words),
+            None => words,
         }
     }
     /// Choose with replacement `n` digits to form and return an [`Option<String>`].
@@ -141,7 +383,7 @@ where
                 clippy::unwrap_used,
                 reason = "as DIGIT_ALPHABET is const, it is not empty and should not provide bad size hints"
             )]
-            buf.push(DIGIT_ALPHABET.choose(&mut self.rng).unwrap());
+            buf.push(rng::choose(&mut self.rng.0, &DIGIT_ALPHABET).unwrap());
         }
         Some(buf.into_iter().collect())
     }
@@ -152,83 +394,587 @@ where
         let after = self.choose_n_digits(self.config.digits_after as usize);
         (before, after)
     }
+    /// Create the groups of digits and symbols for `gaps` gaps between words,
+    /// leaving gaps without a group empty.
+    ///
+    /// [`GroupPlacement::RandomGaps`] chooses which gaps get a group before choosing any digits.
+    fn create_groups(&mut self, gaps: usize) -> Vec<String> {
+        let mut has_group = vec![false; gaps];
+        match self.config.group_placement {
+            GroupPlacement::None => return vec![String::new(); gaps],
+            GroupPlacement::EveryGap => has_group.fill(true),
+            GroupPlacement::RandomGaps => {
+                let mut positions: Vec<usize> = (0..gaps).collect();
+                let count = gaps.min(self.config.group_count as usize);
+                // a partial Fisher-Yates shuffle, leaving the chosen gaps at the start
+                for i in 0..count {
+                    let j = i + self.rng.0.random_index(gaps - i);
+                    positions.swap(i, j);
+                }
+                for gap in &positions[..count] {
+                    has_group[*gap] = true;
+                }
+            }
+        }
+        has_group
+            .into_iter()
+            .map(|has_group| {
+                if has_group {
+                    self.create_group()
+                } else {
+                    String::new()
+                }
+            })
+            .collect()
+    }
+    /// Choose the configured number of digits, followed by the configured number of symbols.
+    fn create_group(&mut self) -> String {
+        let mut group = self
+            .choose_n_digits(self.config.group_digits as usize)
+            .unwrap_or_default();
+        for _ in 0..self.config.group_symbols {
+            if let Some(symbol) = rng::choose(&mut self.rng.0, &self.config.symbol_characters) {
+                group.push(*symbol);
+            }
+        }
+        group
+    }
     /// Choose a separator character from the configured choices.
     fn choose_separator(&mut self) -> Option<char> {
-        self.config
-            .separator_characters
-            .choose(&mut self.rng)
-            .copied()
+        rng::choose(&mut self.rng.0, &self.config.separator_characters).copied()
+    }
+    /// Choose the separators for `gaps` gaps between words and pseudo-words,
+    /// along with the separator character if the same one was placed in every gap.
+    ///
+    /// [`SeparatorType::Single`] chooses one character for every gap, even when there are none.
+    /// [`SeparatorType::PerGap`] and [`SeparatorType::Tokens`] choose each gap's separator separately.
+    fn create_separators(&mut self, gaps: usize) -> Vec<String> {
+        match self.config.separator_type {
+            SeparatorType::Single => {
+                let separator = self.choose_separator();
+                vec![separator.map(String::from).unwrap_or_default(); gaps]
+            }
+            SeparatorType::PerGap => (0..gaps)
+                .map(|_| {
+                    self.choose_separator()
+                        .map(String::from)
+                        .unwrap_or_default()
+                })
+                .collect(),
+            SeparatorType::Tokens => (0..gaps)
+                .map(|_| {
+                    rng::choose(&mut self.rng.0, &self.config.separator_tokens)
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect(),
+        }
     }
     /// Given the password we have created thus far, create the before and after padding.
     /// [`PaddingType::Fixed`] prepends and appends an equal number of padding characters.
     /// [`PaddingType::Adaptive`] will append padding characters to meet the desired length.
     /// Note that if the desired length is shorter than the unpadded password, adaptive
     /// padding is a no-op.
+    /// [`PaddingType::Symmetric`] prepends distinct padding characters and appends their mirror image.
+    /// [`PaddingMode::PerCharacter`] chooses each fixed or adaptive padding character separately.
     fn create_padding(&mut self, password: &str) -> (Option<String>, Option<String>) {
         let len = self.config.padding_length as usize;
         let (before_len, after_len) = match self.config.padding_type {
             PaddingType::None => (0, 0),
             PaddingType::Fixed => (len, len),
             PaddingType::Adaptive => (0, len.saturating_sub(password.chars().count())),
+            PaddingType::Symmetric => return self.create_symmetric_padding(len),
         };
-        let padding_characters = self.config.padding_characters.choose(&mut self.rng);
-        let before = iter::repeat(padding_characters).take(before_len).collect();
-        let after = iter::repeat(padding_characters).take(after_len).collect();
+        if self.config.padding_mode == PaddingMode::PerCharacter {
+            let before = self.choose_padding_characters(before_len);
+            let after = self.choose_padding_characters(after_len);
+            return (before, after);
+        }
+        let padding_characters = rng::choose(&mut self.rng.0, &self.config.padding_characters);
+        let before = iter::repeat_n(padding_characters, before_len).collect();
+        let after = iter::repeat_n(padding_characters, after_len).collect();
         (before, after)
     }
+    /// Choose with replacement `n` padding characters.
+    fn choose_padding_characters(&mut self, n: usize) -> Option<String> {
+        (0..n)
+            .map(|_| rng::choose(&mut self.rng.0, &self.config.padding_characters).copied())
+            .collect()
+    }
+    /// Choose `len` distinct padding characters in a random order for the front,
+    /// and mirror them for the back, so that `([` is matched by `])`.
+    ///
+    /// There can be no more padding characters than there are characters to choose from.
+    fn create_symmetric_padding(&mut self, len: usize) -> (Option<String>, Option<String>) {
+        let mut choices = self.config.padding_characters.clone();
+        if choices.is_empty() {
+            return (None, None);
+        }
+        let len = len.min(choices.len());
+        // a partial Fisher-Yates shuffle, leaving the chosen characters at the start
+        for i in 0..len {
+            let j = i + self.rng.0.random_index(choices.len() - i);
+            choices.swap(i, j);
+        }
+        let before: String = choices[..len].iter().collect();
+        let after: String = choices[..len].iter().rev().map(|c| mirror(*c)).collect();
+        (Some(before), Some(after))
+    }
     /// Create a password.
     ///
     /// The password generation algorithm is very similar to the one found in Crypt::HSXKPasswd,
     /// see [https://metacpan.org/pod/Crypt::HSXKPasswd](https://metacpan.org/pod/Crypt::HSXKPasswd) or below for a local copy:
     ///
-    /// 1. Pick random words from the dictionary.
+    /// 1. Pick random words from the dictionary, short enough to fit in the policy's maximum length.
     /// 2. Apply transformations to the words.
     /// 3. Create pseudo-words made up for randomly chosen digits and add them as the first and last words.
+    ///    Depending on the [`GroupPlacement`], pseudo-words made of digits and symbols may also go between words.
     /// 4. Insert a copy of the same symbol between each of the words and pseudo-words. This symbol is referred to as the separator character.
+    ///    Depending on the [`SeparatorType`], each gap may instead get its own symbol or token.
     /// 5. Pad the password with multiple instances of the same symbol front and/or back. This symbol is referred to as the padding character.
-    pub fn make_password(&mut self) -> String {
-        let filtered_word_indices = self.filter_wordlist();
-        let chosen_words = self.choose_words(&filtered_word_indices);
-        let mut transformed_words = self.transform_words(chosen_words);
+    /// 6. Start again if the password breaks the policy.
+    ///
+    /// It is an error if the policy cannot be met, see [`PasswordMaker::policy_cost`].
+    pub fn generate_password(&mut self) -> Result<GeneratedPassword, ValidationError> {
+        let cost = self.policy_cost()?;
+        let choices = WordChoices::new(&self.config, &self.wordlist)?;
+        self.generate_policy_password(&choices, cost)
+    }
+    /// Create passwords until one meets the policy,
+    /// which the caller has checked can be met at a cost of `policy_cost` bits.
+    ///
+    /// It is an error if the RNG fails, see [`PasswordRng::take_error`].
+    fn generate_policy_password(
+        &mut self,
+        choices: &WordChoices,
+        policy_cost: f64,
+    ) -> Result<GeneratedPassword, ValidationError> {
+        loop {
+            let mut generated = self.generate_candidate(choices);
+            if let Some(e) = self.rng.0.take_error() {
+                return Err(ValidationError::Message(format!(
+                    "unable to make random choices: {e}"
+                )));
+            }
+            if self.config.policy.allows(&generated.to_string()) {
+                generated.entropy.policy = policy_cost;
+                return Ok(generated);
+            }
+        }
+    }
+    /// Create a password without regard for the policy, see [`PasswordMaker::generate_password`].
+    fn generate_candidate(&mut self, choices: &WordChoices) -> GeneratedPassword {
+        if let Some(template) = self.config.template.clone() {
+            let mut generated = self.generate_template_candidate(&template, &choices.indices);
+            generated.entropy = choices.estimate(&self.config);
+            return generated;
+        }
+        let chosen_words = match &choices.slots {
+            Some(slots) => self.choose_budgeted_words(slots),
+            None => self.choose_words(&choices.indices),
+        };
+        let words = self.transform_words(chosen_words.clone());
         let (front_digits, back_digits) = self.create_pseudo_words();
-        let separator = self.choose_separator();
-
-        // begin constructing the password sans padding
-        let mut parts = vec![front_digits.unwrap_or(String::new())];
-        parts.append(&mut transformed_words);
-        parts.push(back_digits.unwrap_or(String::new()));
-        // TODO regression test for: separator should not apply on empty elements
-        let unpadded_password = parts
-            .into_iter()
-            .filter(|p| !p.is_empty())
-            .collect::<Vec<String>>()
-            .join(&separator.map(String::from).unwrap_or_default());
+        let groups = self.create_groups(words.len().saturating_sub(1));
 
-        let (front_padding, rear_padding) = self.create_padding(&unpadded_password);
+        let mut generated = GeneratedPassword {
+            chosen_words,
+            words,
+            front_digits: front_digits.unwrap_or_default(),
+            back_digits: back_digits.unwrap_or_default(),
+            groups,
+            separators: Vec::new(),
+            padding_character: None,
+            front_padding: String::new(),
+            back_padding: String::new(),
+            layout: Layout::Words,
+            entropy: Entropy::default(),
+        };
+        let gaps = generated.parts().len().saturating_sub(1);
+        generated.separators = self.create_separators(gaps);
 
-        [
-            front_padding.unwrap_or(String::new()),
-            unpadded_password,
-            rear_padding.unwrap_or(String::new()),
-        ]
-        .join("")
+        let (front_padding, back_padding) = self.create_padding(&generated.unpadded());
+        let front_padding = front_padding.unwrap_or_default();
+        let back_padding = back_padding.unwrap_or_default();
+        let mut padding = front_padding.chars().chain(back_padding.chars());
+        let first = padding.next();
+        generated.padding_character = first.filter(|first| padding.all(|c| c == *first));
+        generated.entropy = choices.entropy_with_padding(
+            &self.config,
+            front_padding.chars().count() + back_padding.chars().count(),
+        );
+        if entropy::counts_random_leet(&self.config.word_transformation) {
+            let substitutable = generated
+                .chosen_words
+                .iter()
+                .map(|word| word_transformer::leet_substitutable(word, &self.config.leet_table))
+                .sum();
+            generated.entropy = generated.entropy.with_leet_substitutions(substitutable);
+        }
+        if entropy::counts_random_case(&self.config.word_transformation) {
+            let letters: Vec<usize> = generated
+                .chosen_words
+                .iter()
+                .map(|word| entropy::random_case_letters(&self.config, word))
+                .collect();
+            generated.entropy = generated.entropy.with_random_case(&self.config, &letters);
+        }
+        generated.front_padding = front_padding;
+        generated.back_padding = back_padding;
+        generated
     }
-    /// Create passwords.
-    /// This is the public interface for the [`PasswordMaker`] struct.
-    pub fn make_passwords(&mut self) -> Vec<String> {
+    /// Create a password from a template, choosing words from `indices` and everything else
+    /// from the same alphabets as without a template, see [`crate::template`].
+    fn generate_template_candidate(
+        &mut self,
+        template: &Template,
+        indices: &[u32],
+    ) -> GeneratedPassword {
+        let separators = self.create_separators(template.count(|token| *token == Token::Separator));
+        let paddings = template.count(|token| *token == Token::Padding);
+        let padding = match self.config.padding_mode {
+            _ if paddings == 0 => None,
+            PaddingMode::Single => self
+                .choose_padding_characters(1)
+                .map(|padding| padding.repeat(paddings)),
+            PaddingMode::PerCharacter => self.choose_padding_characters(paddings),
+        }
+        .unwrap_or_default();
+
+        let mut pieces = Vec::new();
+        let mut chosen_words = Vec::new();
+        let mut words = Vec::new();
+        let mut next_separator = separators.iter();
+        let mut next_padding = padding.chars();
+        for token in template.tokens() {
+            let piece = match token {
+                Token::LowerWord | Token::CapitalizedWord | Token::UpperWord => {
+                    let chosen = rng::choose(&mut self.rng.0, indices)
+                        .map(|i| self.wordlist[*i as usize].clone())
+                        .unwrap_or_default();
+                    // the chain is applied first, so that the token has the final say on case
+                    let transformed = self.config.transform(&mut self.rng.0, vec![chosen.clone()]);
+                    let mut word = match token {
+                        Token::LowerWord => word_transformer::lower(transformed),
+                        Token::UpperWord => word_transformer::upper(transformed),
+                        _ => word_transformer::capitalize_first(transformed),
+                    };
+                    if let Some(transformer) = &self.transformer {
+                        word = transformer.transform(&mut self.rng.0, word);
+                    }
+                    let word = word.concat();
+                    chosen_words.push(chosen);
+                    words.push(word.clone());
+                    word
+                }
+                Token::Digits(n) => self.choose_n_digits(*n).unwrap_or_default(),
+                Token::Symbol => rng::choose(&mut self.rng.0, &self.config.symbol_characters)
+                    .map(char::to_string)
+                    .unwrap_or_default(),
+                Token::Separator => next_separator.next().cloned().unwrap_or_default(),
+                Token::Padding => next_padding.next().map(String::from).unwrap_or_default(),
+                Token::Literal(text) => text.clone(),
+            };
+            pieces.push(piece);
+        }
+
+        let mut padding_chars = padding.chars();
+        let first = padding_chars.next();
+        GeneratedPassword {
+            chosen_words,
+            words,
+            front_digits: String::new(),
+            back_digits: String::new(),
+            groups: Vec::new(),
+            separators,
+            padding_character: first.filter(|first| padding_chars.all(|c| c == *first)),
+            front_padding: String::new(),
+            back_padding: String::new(),
+            layout: Layout::Template(pieces),
+            entropy: Entropy::default(),
+        }
+    }
+    /// Create a password, discarding everything but the password itself.
+    ///
+    /// See [`PasswordMaker::try_make_password`] to handle errors instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a password cannot be made, see [`PasswordMaker::generate_password`].
+    pub fn make_password(&mut self) -> String {
+        self.try_make_password()
+            .unwrap_or_else(|e| panic!("unable to make a password: {e}"))
+    }
+    /// Create a password, discarding everything but the password itself.
+    ///
+    /// It is an error if a password cannot be made, see [`PasswordMaker::generate_password`].
+    pub fn try_make_password(&mut self) -> Result<String, ValidationError> {
+        self.generate_password()
+            .map(|generated| generated.to_string())
+    }
+    /// Create the configured number of passwords, along with their entropy.
+    ///
+    /// It is an error if the policy cannot be met, see [`PasswordMaker::generate_password`].
+    pub fn generate_passwords(&mut self) -> Result<Vec<GeneratedPassword>, ValidationError> {
+        let cost = self.policy_cost()?;
+        let choices = WordChoices::new(&self.config, &self.wordlist)?;
         let count = self.config.count as usize;
         let mut buf = Vec::with_capacity(count);
         for _ in 0..count {
-            buf.push(self.make_password());
+            buf.push(self.generate_policy_password(&choices, cost)?);
         }
-        buf
+        Ok(buf)
+    }
+    /// Create passwords.
+    /// This is the public interface for the [`PasswordMaker`] struct.
+    ///
+    /// See [`PasswordMaker::try_make_passwords`] to handle errors instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the passwords cannot be made, see [`PasswordMaker::generate_passwords`].
+    pub fn make_passwords(&mut self) -> Vec<String> {
+        self.try_make_passwords()
+            .unwrap_or_else(|e| panic!("unable to make passwords: {e}"))
+    }
+    /// Create passwords.
+    ///
+    /// It is an error if the passwords cannot be made, see [`PasswordMaker::generate_passwords`].
+    pub fn try_make_passwords(&mut self) -> Result<Vec<String>, ValidationError> {
+        Ok(self
+            .generate_passwords()?
+            .into_iter()
+            .map(|generated| generated.to_string())
+            .collect())
+    }
+}
+
+/// The character that mirrors `c`, which is `c` itself unless it is a bracket.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        c => c,
+    }
+}
+
+/// See [`PasswordMaker::filter_wordlist`].
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "u32 MAX is more than enough for any reasonable word list length"
+)]
+fn filter_wordlist(config: &Config, wordlist: &[String]) -> Vec<u32> {
+    let min_len = config.word_min_length as usize;
+    let max_len = config.word_max_length as usize;
+    let banned = config.policy.banned_characters.as_slice();
+    wordlist
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| (min_len..=max_len).contains(&word.chars().count()))
+        .filter(|(_, word)| !word.contains(banned))
+        .map(|(i, _)| i as u32)
+        .collect()
+}
+
+/// The words that may be chosen for a password.
+struct WordChoices {
+    /// every word that fits the length limits and the policy, see [`PasswordMaker::filter_wordlist`]
+    indices: Vec<u32>,
+    /// if the policy has a maximum length, the words that fit in each word's length budget,
+    /// see [`Config::word_length_budgets`]
+    slots: Option<Vec<Vec<u32>>>,
+    /// the fewest characters that [`crate::types::WordTransformationType::RandomLeet`] could substitute in any word
+    min_leet_substitutable: usize,
+    /// the fewest letters that [`crate::types::WordTransformationType::RandomCase`] is counted as deciding the case of in any word
+    min_random_case_letters: usize,
+}
+
+impl WordChoices {
+    /// Work out which words may be chosen.
+    ///
+    /// It is an error if words are needed and none fit the length limits, or if a word's budget fits no words.
+    fn new(config: &Config, wordlist: &[String]) -> Result<Self, ValidationError> {
+        let indices = filter_wordlist(config, wordlist);
+        let words_needed = config
+            .template
+            .as_ref()
+            .map_or(config.word_count as usize, Template::word_count);
+        if indices.is_empty() && words_needed > 0 {
+            let banned = if config.policy.banned_characters.is_empty() {
+                ""
+            } else {
+                " without banned characters"
+            };
+            return Err(ValidationError::Message(format!(
+                "no word in the wordlist is between {} and {} characters{banned}",
+                config.word_min_length, config.word_max_length
+            )));
+        }
+        let min_leet_substitutable = if entropy::counts_random_leet(&config.word_transformation) {
+            indices
+                .iter()
+                .map(|i| {
+                    word_transformer::leet_substitutable(&wordlist[*i as usize], &config.leet_table)
+                })
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let min_random_case_letters = if entropy::counts_random_case(&config.word_transformation) {
+            indices
+                .iter()
+                .map(|i| entropy::random_case_letters(config, &wordlist[*i as usize]))
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        // a template's words are not fitted into the max_length, passwords that are too long are thrown away
+        if config.policy.max_length == 0 || config.template.is_some() || indices.is_empty() {
+            return Ok(Self {
+                indices,
+                slots: None,
+                min_leet_substitutable,
+                min_random_case_letters,
+            });
+        }
+        let slots = config
+            .word_length_budgets()?
+            .into_iter()
+            .map(|budget| {
+                let slot: Vec<u32> = indices
+                    .iter()
+                    .copied()
+                    .filter(|i| wordlist[*i as usize].chars().count() <= budget)
+                    .collect();
+                if slot.is_empty() {
+                    return Err(ValidationError::Message(format!(
+                        "no word is short enough to fit in the max_length, which leaves room for {budget} characters"
+                    )));
+                }
+                Ok(slot)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            indices,
+            slots: Some(slots),
+            min_leet_substitutable,
+            min_random_case_letters,
+        })
+    }
+    /// See [`Entropy::with_padding`].
+    fn entropy_with_padding(&self, config: &Config, padding_count: usize) -> Entropy {
+        self.with_slots(Entropy::with_padding(
+            config,
+            self.indices.len(),
+            padding_count,
+        ))
+    }
+    /// See [`Entropy::estimate`].
+    ///
+    /// Each word chosen for [`crate::types::WordTransformationType::RandomLeet`] or [`crate::types::WordTransformationType::RandomCase`]
+    /// is counted as having as few substitutable characters or letters as any word that may be chosen.
+    fn estimate(&self, config: &Config) -> Entropy {
+        let mut entropy = self.with_slots(Entropy::estimate(config, self.indices.len()));
+        if self.indices.is_empty() {
+            return entropy;
+        }
+        let word_count = config
+            .template
+            .as_ref()
+            .map_or(config.word_count as usize, Template::word_count);
+        if entropy::counts_random_leet(&config.word_transformation) {
+            entropy = entropy.with_leet_substitutions(word_count * self.min_leet_substitutable);
+        }
+        // a template's tokens decide the case of its words
+        if config.template.is_none() && entropy::counts_random_case(&config.word_transformation) {
+            entropy =
+                entropy.with_random_case(config, &vec![self.min_random_case_letters; word_count]);
+        }
+        entropy
+    }
+    /// Count the entropy of each word separately if they are chosen from different words.
+    fn with_slots(&self, entropy: Entropy) -> Entropy {
+        match &self.slots {
+            Some(slots) => {
+                entropy.with_word_choices(&slots.iter().map(Vec::len).collect::<Vec<usize>>())
+            }
+            None => entropy,
+        }
+    }
+}
+
+/// Estimate the entropy of passwords made with `config`, `wordlist` and a custom `transformer`,
+/// including the cost of the policy.
+fn estimate_entropy(
+    config: &Config,
+    wordlist: &[String],
+    transformer: Option<&Arc<dyn WordTransformer>>,
+) -> Result<Entropy, ValidationError> {
+    let mut entropy = WordChoices::new(config, wordlist)?.estimate(config);
+    entropy.policy = sample_policy_cost(config, wordlist, transformer)?;
+    Ok(entropy)
+}
+
+/// Estimate how many bits of entropy are lost by throwing away passwords that break the policy.
+///
+/// The chance of a password meeting the policy is estimated by making [`policy::SAMPLES`]
+/// passwords with a fixed seed, so that the estimate is the same every time.
+/// Keeping only a share `p` of the passwords costs `-log2(p)` bits.
+/// An empty policy never throws passwords away, and costs nothing.
+///
+/// It is an error if no sample meets the policy, as it then either cannot be met
+/// or would take too long to meet.
+/// It is also an error if the words cannot fit in the policy's maximum length.
+pub fn policy_cost(config: &Config, wordlist: &[String]) -> Result<f64, ValidationError> {
+    sample_policy_cost(config, wordlist, None)
+}
+
+/// See [`policy_cost`], sampling passwords that are also transformed by a custom `transformer`.
+fn sample_policy_cost(
+    config: &Config,
+    wordlist: &[String],
+    transformer: Option<&Arc<dyn WordTransformer>>,
+) -> Result<f64, ValidationError> {
+    if config.policy.is_empty() {
+        return Ok(0.0);
     }
+    let choices = WordChoices::new(config, wordlist)?;
+    let mut sampler = PasswordMaker::from_rng(
+        SmallRng::seed_from_u64(policy::SAMPLE_SEED),
+        config.clone(),
+        wordlist.to_vec(),
+    );
+    sampler.transformer = transformer.cloned();
+    let met = (0..policy::SAMPLES)
+        .filter(|_| {
+            let generated = sampler.generate_candidate(&choices);
+            config.policy.allows(&generated.to_string())
+        })
+        .count();
+    if met == 0 {
+        return Err(ValidationError::UnsatisfiablePolicy(policy::SAMPLES));
+    }
+    Ok((policy::SAMPLES as f64 / met as f64).log2())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consts::default;
+    use crate::rng::RandomChoice;
     use crate::test_helpers::*;
+    use crate::types::CharacterClass;
+    use crate::types::UppercaseLimitType;
+    use crate::types::WordTransformationType;
+    use rand::TryRngCore;
 
     #[test]
     fn test_filter_wordlist() {
@@ -264,6 +1010,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_no_word_fits() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.word_min_length = 7;
+        maker.config.word_max_length = 9;
+        let err = maker.generate_password().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no word in the wordlist is between 7 and 9 characters"
+        );
+        assert!(maker.entropy().is_err());
+        // no words are needed
+        maker.config.word_count = 0;
+        assert!(maker.generate_password().is_ok());
+    }
+
     /// It is possible to incorrectly use rand methods such that
     /// you choose random items but place them in a non-random order.
     #[test]
@@ -290,7 +1052,7 @@ mod tests {
     #[test]
     fn test_transform_words_none() {
         let mut maker = make_seeded_maker(1);
-        maker.config.word_transformation = WordTransformationType::None;
+        maker.config.word_transformation = Vec::new();
         let v = vec!["abCD".to_owned()];
         assert_eq!(v, maker.transform_words(v.clone()));
     }
@@ -421,6 +1183,669 @@ mod tests {
         assert_eq!("?????", &right.unwrap());
     }
     #[test]
+    fn test_create_padding_per_character() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.padding_mode = PaddingMode::PerCharacter;
+        maker.config.padding_length = 20;
+        maker.config.padding_characters = vec!['!', '@'];
+        let (left, right) = maker.create_padding("");
+        let (left, right) = (left.unwrap(), right.unwrap());
+        assert_eq!(left.len(), 20);
+        assert_eq!(right.len(), 20);
+        assert!(left.contains('!') && left.contains('@'));
+        maker.config.padding_type = PaddingType::Adaptive;
+        let (left, right) = maker.create_padding("Hello");
+        assert_eq!("", &left.unwrap());
+        assert_eq!(right.unwrap().len(), 15);
+        maker.config.padding_characters = Vec::new();
+        let (_, right) = maker.create_padding("Hello");
+        assert!(right.is_none());
+    }
+    #[test]
+    fn test_generate_password_per_character_padding() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.padding_mode = PaddingMode::PerCharacter;
+        maker.config.padding_length = 3;
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.front_padding_length(), 3);
+        assert_eq!(generated.back_padding_length(), 3);
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+        maker.config.padding_type = PaddingType::Adaptive;
+        maker.config.padding_length = 60;
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.len(), 60);
+        // the padding actually added is counted, which is at least the estimate
+        let padding = generated.back_padding.chars().count() as f64;
+        assert!((generated.entropy.padding - padding * 18f64.log2()).abs() < 1e-9);
+        assert!(generated.entropy.padding >= maker.entropy().unwrap().padding);
+    }
+    #[test]
+    fn test_create_padding_symmetric() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.padding_type = PaddingType::Symmetric;
+        maker.config.padding_length = 4;
+        maker.config.padding_characters = "*#$[".chars().collect();
+        let (left, right) = maker.create_padding("");
+        let (left, right) = (left.unwrap(), right.unwrap());
+        assert_eq!(left.chars().count(), 4);
+        let mut sorted: Vec<char> = left.chars().collect();
+        sorted.sort_unstable();
+        assert_eq!(sorted, ['#', '$', '*', '[']);
+        let mirrored: String = left
+            .chars()
+            .rev()
+            .map(|c| if c == '[' { ']' } else { c })
+            .collect();
+        assert_eq!(right, mirrored);
+    }
+    #[test]
+    fn test_create_padding_symmetric_brackets() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.padding_type = PaddingType::Symmetric;
+        maker.config.padding_length = 8;
+        maker.config.padding_characters = "()[]{}<>".chars().collect();
+        let (left, right) = maker.create_padding("");
+        let password = format!("{}{}", left.unwrap(), right.unwrap());
+        // every bracket is closed in the right order
+        let mut stack = Vec::new();
+        for c in password.chars() {
+            if stack.last() == Some(&mirror(c)) {
+                stack.pop();
+            } else {
+                stack.push(c);
+            }
+        }
+        assert!(stack.is_empty(), "{password}");
+    }
+    #[test]
+    fn test_create_padding_symmetric_not_enough_characters() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.padding_type = PaddingType::Symmetric;
+        maker.config.padding_length = 3;
+        maker.config.padding_characters = vec!['<'];
+        let (left, right) = maker.create_padding("");
+        assert_eq!("<", &left.unwrap());
+        assert_eq!(">", &right.unwrap());
+        maker.config.padding_characters = Vec::new();
+        let (left, right) = maker.create_padding("");
+        assert!(left.is_none());
+        assert!(right.is_none());
+    }
+    #[test]
+    fn test_generate_password_symmetric() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.padding_type = PaddingType::Symmetric;
+        maker.config.padding_length = 2;
+        maker.config.padding_characters = vec!['[', '{'];
+        let generated = maker.generate_password().unwrap();
+        assert!(
+            generated.to_string().starts_with("[{") && generated.to_string().ends_with("}]")
+                || generated.to_string().starts_with("{[") && generated.to_string().ends_with("]}")
+        );
+        assert_eq!(generated.padding_character, None);
+        assert_eq!(generated.len(), generated.unpadded().len() + 4);
+        // two orders to choose from
+        assert!((generated.entropy.padding - 1.0).abs() < 1e-9);
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+    }
+    #[test]
+    fn test_generate_password_separators_per_gap() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.separator_type = SeparatorType::PerGap;
+        maker.config.separator_characters = vec!['-', '.'];
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.separators.len(), 4);
+        assert!(generated.separators.iter().all(|s| s == "-" || s == "."));
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+        // the same character every time is still reported
+        maker.config.separator_characters = vec!['-'];
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.separator(), Some('-'));
+        assert_eq!(generated.separators, ["-", "-", "-", "-"]);
+    }
+    #[test]
+    fn test_generate_password_separator_tokens() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.separator_type = SeparatorType::Tokens;
+        maker.config.separator_tokens = vec!["::".to_owned(), " and ".to_owned()];
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.separators.len(), 4);
+        assert!(
+            generated
+                .separators
+                .iter()
+                .all(|s| s == "::" || s == " and ")
+        );
+        assert_eq!(generated.separator(), None);
+        let unpadded = generated.parts().join("");
+        let separators: usize = generated.separators.iter().map(|s| s.len()).sum();
+        assert_eq!(generated.unpadded().len(), unpadded.len() + separators);
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+        // the longest token is left room for
+        maker.config.policy.max_length = 40;
+        for generated in maker.generate_passwords().unwrap() {
+            assert!(generated.len() <= 40);
+        }
+        maker.config.separator_tokens = Vec::new();
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.separators, ["", "", "", ""]);
+    }
+    #[test]
+    fn test_create_groups() {
+        let mut maker = make_seeded_maker(1);
+        assert_eq!(maker.create_groups(3), ["", "", ""]);
+        maker.config.group_placement = GroupPlacement::EveryGap;
+        maker.config.group_digits = 2;
+        maker.config.group_symbols = 1;
+        maker.config.symbol_characters = vec!['#'];
+        let groups = maker.create_groups(3);
+        assert_eq!(groups.len(), 3);
+        assert!(
+            groups
+                .iter()
+                .all(|group| group.len() == 3 && group.ends_with('#'))
+        );
+        maker.config.group_placement = GroupPlacement::RandomGaps;
+        maker.config.group_count = 2;
+        let groups = maker.create_groups(3);
+        assert_eq!(groups.iter().filter(|group| group.is_empty()).count(), 1);
+        assert_eq!(maker.create_groups(0), Vec::<String>::new());
+    }
+    #[test]
+    fn test_generate_password_groups() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.group_placement = GroupPlacement::EveryGap;
+        maker.config.digits_after = 0;
+        maker.config.separator_characters = Vec::new();
+        maker.config.padding_type = PaddingType::None;
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.groups.len(), 3);
+        assert_eq!(generated.parts().len(), 7);
+        let mut expected = generated.words[0].clone();
+        for (group, word) in generated.groups.iter().zip(&generated.words[1..]) {
+            expected.push_str(group);
+            expected.push_str(word);
+        }
+        assert_eq!(generated.to_string(), expected);
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+        // with separators, every group is separated like a word
+        maker.config.separator_characters = vec!['-'];
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.to_string().matches('-').count(), 6);
+    }
+    #[test]
+    fn test_generate_password_template() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.template =
+            Some(Template::parse("{Word}{sep}{word}{sep}{dd}{sym}{WORD}{{{pad}}}").unwrap());
+        maker.config.separator_characters = vec!['-'];
+        maker.config.symbol_characters = vec!['#'];
+        maker.config.padding_characters = vec!['*'];
+        let generated = maker.generate_password().unwrap();
+        let [first, second, third] = generated.words.as_slice() else {
+            panic!("expected 3 words, got {:?}", generated.words);
+        };
+        let digits: String = generated
+            .to_string()
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect();
+        assert_eq!(digits.len(), 2);
+        assert_eq!(
+            generated.to_string(),
+            format!("{first}-{second}-{digits}#{third}{{*}}")
+        );
+        assert!(first.chars().next().unwrap().is_uppercase());
+        assert!(str_is_lowercase(second));
+        assert!(str_is_uppercase(third));
+        assert_eq!(generated.chosen_words[2].to_uppercase(), *third);
+        assert_eq!(generated.separator(), Some('-'));
+        assert_eq!(generated.padding_character, Some('*'));
+        // the pieces made from the tokens are the whole password
+        assert_eq!(generated.parts().concat(), generated.to_string());
+        assert_eq!(generated.len(), generated.to_string().chars().count());
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+    }
+    #[test]
+    fn test_generate_password_template_transformation() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.template = Some(Template::parse("{word}{sep}{WORD}").unwrap());
+        maker.config.word_transformation = vec![
+            WordTransformationType::Upper,
+            WordTransformationType::Leet,
+            WordTransformationType::Reverse,
+        ];
+        let generated = maker.generate_password().unwrap();
+        for (chosen, word) in generated.chosen_words.iter().zip(&generated.words) {
+            let expected: String =
+                word_transformer::leet(vec![chosen.clone()], &maker.config.leet_table)
+                    .concat()
+                    .chars()
+                    .rev()
+                    .collect();
+            assert_eq!(word.to_lowercase(), expected);
+        }
+        assert!(!generated.words[0].chars().any(char::is_uppercase));
+        assert!(!generated.words[1].chars().any(char::is_lowercase));
+        // random leet is counted for every word of the template,
+        // every word in the small list having at least one character to substitute
+        maker.wordlist = make_wordlist();
+        let plain = maker.entropy().unwrap();
+        maker.config.word_transformation = vec![WordTransformationType::RandomLeet];
+        let entropy = maker.entropy().unwrap();
+        assert!(entropy.word_transformation > plain.word_transformation);
+        assert_eq!(maker.generate_password().unwrap().entropy, entropy);
+    }
+    #[test]
+    fn test_generate_password_template_no_word_fits() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.template = Some(Template::parse("{word}").unwrap());
+        maker.config.word_min_length = 20;
+        maker.config.word_max_length = 25;
+        assert!(maker.generate_password().is_err());
+        maker.config.template = Some(Template::parse("{dd}").unwrap());
+        assert!(maker.generate_password().is_ok());
+    }
+    #[test]
+    fn test_generate_password_template_policy() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.template = Some(Template::parse("{word}{d}").unwrap());
+        maker.config.policy.max_length = 6;
+        for generated in maker.generate_passwords().unwrap() {
+            assert!(generated.len() <= 6);
+        }
+        assert!(maker.policy_cost().unwrap() > 0.0);
+    }
+    #[test]
+    fn test_generate_password_entropy() {
+        let mut maker = make_seeded_maker_big_list(1);
+        let generated = maker.generate_password().unwrap();
+        assert_eq!("+startling;SHAFT;cactus;SHACK;15+", generated.to_string());
+        assert_eq!(generated.entropy, maker.entropy().unwrap());
+    }
+    #[test]
+    fn test_generate_password_parts() {
+        let mut maker = make_seeded_maker_big_list(1);
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(
+            generated.chosen_words,
+            ["startling", "shaft", "cactus", "shack"]
+        );
+        assert_eq!(generated.words, ["startling", "SHAFT", "cactus", "SHACK"]);
+        assert_eq!(generated.front_digits, "");
+        assert_eq!(generated.back_digits, "15");
+        assert_eq!(generated.front_padding, "+");
+        assert_eq!(generated.back_padding, "+");
+        assert_eq!(generated.front_padding_length(), 1);
+        assert_eq!(generated.back_padding_length(), 1);
+        assert_eq!(
+            generated.parts(),
+            ["startling", "SHAFT", "cactus", "SHACK", "15"]
+        );
+        assert_eq!(generated.unpadded(), "startling;SHAFT;cactus;SHACK;15");
+        assert_eq!(generated.len(), 33);
+        assert_eq!(generated.separator(), Some(';'));
+        assert_eq!(generated.padding_character, Some('+'));
+        maker.config.padding_type = PaddingType::None;
+        maker.config.separator_characters = Vec::new();
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.separator(), None);
+        assert_eq!(generated.padding_character, None);
+    }
+    #[cfg(feature = "dice")]
+    #[test]
+    fn test_generate_password_dice() {
+        use crate::rng::DiceRng;
+        let rolls = "11111 11111 66666 12345\n66 11 16\n11 66";
+        let mut maker = PasswordMaker::from_rng(
+            DiceRng::new(Box::new(std::io::Cursor::new(rolls)), None),
+            ConfigBuilder::new().build().unwrap(),
+            WORDLIST.iter().map(|s| String::from(*s)).collect(),
+        );
+        // 1-2-3-4-5 is "arousal" in the numbered EFF list,
+        // digits use 2 dice each, 6-6 being rejected as biased
+        assert_eq!(maker.make_password(), ";abacus!ABACUS!zoom!AROUSAL!05;");
+        // running out of rolls is an error, and so is a line that is not rolls
+        for rolls in ["11111 11111", "11111 11111 66666 12345\nheads"] {
+            let mut maker = PasswordMaker::from_rng(
+                DiceRng::new(Box::new(std::io::Cursor::new(rolls)), None),
+                ConfigBuilder::new().build().unwrap(),
+                WORDLIST.iter().map(|s| String::from(*s)).collect(),
+            );
+            let err = maker.generate_password().unwrap_err().to_string();
+            assert!(err.starts_with("unable to make random choices: "), "{err}");
+        }
+    }
+    /// `--seed` promises the same passwords for the same seed,
+    /// so changes to the algorithm or the order of random choices must be deliberate.
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn test_seeded_chacha20_is_reproducible() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+        let config = ConfigBuilder::new()
+            .count(Some("2".to_owned()))
+            .build()
+            .unwrap();
+        let mut maker =
+            PasswordMaker::from_rng(ChaCha20Rng::seed_from_u64(5), config, default_wordlist());
+        assert_eq!(
+            maker.make_passwords(),
+            [
+                "?profanity/POSSIBLY/manhole/ZIPFILE/28?",
+                "~molehill&SECRECY&plus&UNPLANNED&61~"
+            ]
+        );
+    }
+    /// Separators should not be placed around empty pseudo-words.
+    #[test]
+    fn test_generated_password_display() {
+        let generated = GeneratedPassword {
+            chosen_words: vec!["correct".to_owned(), "horse".to_owned()],
+            words: vec!["Correct".to_owned(), "Horse".to_owned()],
+            front_digits: String::new(),
+            back_digits: "42".to_owned(),
+            groups: vec![String::new()],
+            separators: vec!["-".to_owned(); 2],
+            padding_character: Some('*'),
+            front_padding: "**".to_owned(),
+            back_padding: "***".to_owned(),
+            layout: Layout::Words,
+            entropy: Entropy::default(),
+        };
+        assert_eq!(generated.to_string(), "**Correct-Horse-42***");
+        assert_eq!(generated.len(), 21);
+        let generated = GeneratedPassword {
+            words: Vec::new(),
+            separators: Vec::new(),
+            padding_character: None,
+            front_padding: String::new(),
+            back_padding: String::new(),
+            back_digits: String::new(),
+            ..generated
+        };
+        assert!(generated.is_empty());
+        assert_eq!(generated.to_string(), "");
+    }
+    #[test]
+    fn test_generate_password_entropy_adaptive() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.padding_type = PaddingType::Adaptive;
+        maker.config.padding_length = 1;
+        // the estimate cannot be sure padding will be applied
+        assert!(maker.entropy().unwrap().padding == 0.0);
+        let generated = maker.generate_password().unwrap();
+        assert!(generated.entropy.padding == 0.0);
+        maker.config.padding_length = 100;
+        let generated = maker.generate_password().unwrap();
+        assert_eq!(generated.to_string().chars().count(), 100);
+        assert_eq!(generated.len(), 100);
+        assert!(generated.entropy.padding > 0.0);
+    }
+    #[test]
+    fn test_filter_wordlist_banned_characters() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.policy.banned_characters = vec!['o'];
+        let words: Vec<&str> = maker
+            .filter_wordlist()
+            .into_iter()
+            .map(|i| maker.wordlist[i as usize].as_str())
+            .collect();
+        assert_eq!(words, ["water", "fire", "deep", "ice", "pie"]);
+    }
+    #[test]
+    fn test_generate_password_policy() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.count = 20;
+        maker.config.word_transformation = vec![WordTransformationType::RandomUpperLower];
+        maker.config.policy.max_length = 28;
+        maker.config.policy.required_characters = vec![CharacterClass::Upper];
+        let cost = maker.policy_cost().unwrap();
+        assert!(cost > 0.0);
+        for generated in maker.generate_passwords().unwrap() {
+            assert!(generated.len() <= 28, "{generated}");
+            assert!(generated.to_string().chars().any(char::is_uppercase));
+            assert!(generated.entropy.policy == cost);
+        }
+        assert_eq!(maker.entropy().unwrap().policy, cost);
+    }
+    /// The estimate must not depend on the RNG, so that entropy is reported consistently.
+    #[test]
+    fn test_policy_cost_is_stable() {
+        let mut maker = make_seeded_maker_big_list(1);
+        assert!(maker.policy_cost().unwrap() == 0.0);
+        maker.config.policy.max_repeated = 1;
+        let cost = maker.policy_cost().unwrap();
+        maker.rng = SmallRng::seed_from_u64(2).unwrap_err();
+        assert!(maker.policy_cost().unwrap() == cost);
+        // padding is repeated 2 times in a row
+        maker.config.padding_length = 2;
+        let err = maker.policy_cost().unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::UnsatisfiablePolicy(policy::SAMPLES)
+        ));
+        assert!(maker.generate_password().is_err());
+        assert!(maker.generate_passwords().is_err());
+        assert!(maker.entropy().is_err());
+        maker.config.padding_length = 1;
+        assert!(maker.policy_cost().unwrap() == cost);
+    }
+    #[test]
+    fn test_policy_cost_follows_wordlist() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.policy.required_characters = vec![CharacterClass::Upper];
+        maker.config.word_transformation = Vec::new();
+        assert!(maker.policy_cost().is_err());
+        maker.wordlist = vec!["HELLO".to_owned()];
+        assert!(maker.policy_cost().unwrap() == 0.0);
+    }
+    #[test]
+    fn test_resolve_min_entropy_policy() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.word_transformation = vec![WordTransformationType::RandomUpperLower];
+        maker.config.min_entropy = 56;
+        maker.resolve_min_entropy().unwrap();
+        assert_eq!(maker.config.word_count, 3);
+        // throwing passwords away and choosing shorter words both cost entropy,
+        // which has to be made up for with another word
+        maker.config.policy.required_characters =
+            vec![CharacterClass::Upper, CharacterClass::Lower];
+        maker.config.policy.max_length = 30;
+        maker.resolve_min_entropy().unwrap();
+        assert_eq!(maker.config.word_count, 4);
+        let entropy = maker.entropy().unwrap();
+        assert!(entropy.total() >= 56.0);
+        assert!(entropy.policy > 0.0);
+        maker.config.policy.max_length = 20;
+        let err = maker.resolve_min_entropy().unwrap_err();
+        assert!(matches!(err, ValidationError::UnreachableEntropy(56, _)));
+    }
+    #[test]
+    fn test_generate_password_leet() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.count = 20;
+        maker.config.word_transformation = vec![WordTransformationType::Leet];
+        for generated in maker.generate_passwords().unwrap() {
+            assert_eq!(
+                generated.words,
+                word_transformer::leet(generated.chosen_words.clone(), &default::LEET_TABLE)
+            );
+            assert!(generated.entropy.word_transformation == 0.0);
+        }
+        maker.config.word_transformation = vec![WordTransformationType::RandomLeet];
+        // "world" has the fewest substitutable characters, only its `o`
+        let estimate = maker.entropy().unwrap();
+        assert!(estimate.word_transformation == 4.0);
+        for generated in maker.generate_passwords().unwrap() {
+            let substitutable: usize = generated
+                .chosen_words
+                .iter()
+                .map(|word| word_transformer::leet_substitutable(word, &default::LEET_TABLE))
+                .sum();
+            assert!(generated.entropy.word_transformation == substitutable as f64);
+            assert!(generated.entropy.total() >= estimate.total());
+        }
+    }
+    /// Add a random digit to the end of every word.
+    #[derive(Debug)]
+    struct DigitSuffix;
+
+    impl WordTransformer for DigitSuffix {
+        fn transform(&self, rng: &mut dyn RandomChoice, words: Vec<String>) -> Vec<String> {
+            words
+                .into_iter()
+                .map(|word| format!("{word}{}", rng.choose_index(10)))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_generate_password_custom_transformer() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.count = 20;
+        maker.config.word_transformation = vec![WordTransformationType::Upper];
+        maker.config.digits_after = 0;
+        maker.config.policy.required_characters = vec![CharacterClass::Digit];
+        // only the custom transformer adds digits
+        assert!(matches!(
+            maker.policy_cost().unwrap_err(),
+            ValidationError::UnsatisfiablePolicy(_)
+        ));
+        let mut maker = maker.with_transformer(DigitSuffix);
+        assert!(maker.policy_cost().unwrap() == 0.0);
+        let entropy = maker.entropy().unwrap();
+        for generated in maker.generate_passwords().unwrap() {
+            for (word, chosen) in generated.words.iter().zip(&generated.chosen_words) {
+                let (upper, digit) = word.split_at(chosen.len());
+                assert_eq!(upper, chosen.to_uppercase());
+                assert!(digit.len() == 1 && digit.chars().all(|c| c.is_ascii_digit()));
+            }
+            // the custom transformer's random choices are not counted
+            assert_eq!(generated.entropy, entropy);
+        }
+        // with a template, each word is transformed after its token sets the case
+        maker.config.word_transformation = Vec::new();
+        maker.config.template = Some(Template::parse("{Word}{sep}{word}").unwrap());
+        let entropy = maker.entropy().unwrap();
+        for generated in maker.generate_passwords().unwrap() {
+            let [first, second] = generated.words.as_slice() else {
+                panic!("expected 2 words, got {:?}", generated.words);
+            };
+            assert!(first.starts_with(
+                &word_transformer::capitalize_first(vec![generated.chosen_words[0].clone()])[0]
+            ));
+            assert!(second.starts_with(&generated.chosen_words[1]));
+            for word in &generated.words {
+                assert!(word.ends_with(|c: char| c.is_ascii_digit()), "{word}");
+            }
+            assert_eq!(generated.entropy, entropy);
+        }
+    }
+    #[test]
+    fn test_generate_password_transformation_chain() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.count = 20;
+        maker.config.word_transformation = vec![
+            WordTransformationType::CapitalizeFirst,
+            WordTransformationType::RandomLeet,
+            WordTransformationType::Reverse,
+        ];
+        let estimate = maker.entropy().unwrap();
+        for generated in maker.generate_passwords().unwrap() {
+            for (word, chosen) in generated.words.iter().zip(&generated.chosen_words) {
+                assert_eq!(word.chars().count(), chosen.chars().count());
+                let last = word.chars().last().unwrap();
+                let first = chosen.chars().next().unwrap();
+                assert!(
+                    last == first.to_ascii_uppercase()
+                        || last.is_ascii_punctuation()
+                        || last.is_ascii_digit()
+                );
+            }
+            assert!(generated.entropy.total() >= estimate.total());
+        }
+        maker.config.word_transformation = vec![
+            WordTransformationType::Upper,
+            WordTransformationType::Reverse,
+        ];
+        for generated in maker.generate_passwords().unwrap() {
+            assert_eq!(
+                generated.words,
+                word_transformer::reverse(word_transformer::upper(generated.chosen_words.clone()))
+            );
+        }
+    }
+    #[test]
+    fn test_generate_password_random_case() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.count = 20;
+        maker.config.word_transformation = vec![WordTransformationType::RandomCase];
+        // "ice" and "pie" have the fewest letters
+        let estimate = maker.entropy().unwrap();
+        assert!(estimate.word_transformation == 12.0);
+        for generated in maker.generate_passwords().unwrap() {
+            let letters: usize = generated.chosen_words.iter().map(String::len).sum();
+            assert!(generated.entropy.word_transformation == letters as f64);
+            assert!(generated.entropy.total() >= estimate.total());
+        }
+        maker.config.uppercase_limit = 1;
+        maker.config.uppercase_limit_type = UppercaseLimitType::Password;
+        let estimate = maker.entropy().unwrap();
+        assert!(estimate.word_transformation == 12f64.log2());
+        for generated in maker.generate_passwords().unwrap() {
+            let uppercase = generated
+                .words
+                .concat()
+                .chars()
+                .filter(char::is_ascii_uppercase)
+                .count();
+            assert_eq!(uppercase, 1);
+            assert!(generated.entropy.total() >= estimate.total());
+        }
+    }
+    #[test]
+    fn test_generate_password_max_length() {
+        let mut maker = make_seeded_maker_big_list(1);
+        let unconstrained = maker.entropy().unwrap();
+        maker.config.count = 50;
+        maker.config.policy.max_length = 24;
+        let entropy = maker.entropy().unwrap();
+        assert!(entropy.words < unconstrained.words);
+        assert!(entropy.policy == 0.0);
+        for generated in maker.generate_passwords().unwrap() {
+            assert!(generated.len() <= 24, "{generated}");
+            // budgets of 4, 4, 4 and 4 characters
+            assert!(generated.chosen_words.iter().all(|word| word.len() <= 4));
+            assert_eq!(generated.entropy, entropy);
+        }
+    }
+    #[test]
+    fn test_max_length_no_word_fits() {
+        let mut maker = make_seeded_maker(1);
+        maker.config.word_min_length = 1;
+        maker.config.policy.max_length = 20;
+        // 4 words of at most 3 characters, only "ice" and "pie" fit
+        assert!(maker.policy_cost().is_ok());
+        maker.wordlist.retain(|word| word.len() > 3);
+        let err = maker.policy_cost().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no word is short enough to fit in the max_length, which leaves room for 3 characters"
+        );
+    }
+    #[test]
+    fn test_with_wordlist() {
+        let config = ConfigBuilder::new()
+            .word_transformation(Some("none".to_owned()))
+            .build()
+            .unwrap();
+        let mut maker =
+            PasswordMaker::<rand::rngs::OsRng>::with_wordlist(config, vec!["zebra".to_owned()]);
+        let password = maker.make_password();
+        assert_eq!(password.matches("zebra").count(), 4);
+    }
+    #[test]
     fn test_make_password_default() {
         let mut maker = make_seeded_maker_big_list(1);
         let password = maker.make_password();
@@ -433,6 +1858,14 @@ mod tests {
         assert_eq!("+startling;SHAFT;cactus;SHACK;15+", &passwords[0]);
     }
     #[test]
+    fn test_try_make_passwords_error() {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config.word_min_length = 20;
+        maker.config.word_max_length = 20;
+        assert!(maker.try_make_password().is_err());
+        assert!(maker.try_make_passwords().is_err());
+    }
+    #[test]
     fn test_create_3_passwords() {
         let mut maker = make_seeded_maker_big_list(1);
         maker.config.count = 3;
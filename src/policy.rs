@@ -0,0 +1,136 @@
+//! Rules that passwords must follow, such as those enforced by websites.
+//!
+//! Banned characters are removed from the separator and padding characters when a
+//! [`crate::config::Config`] is built, and words containing them are never chosen.
+//! The maximum length is shared out between the words, which are only chosen from those that fit,
+//! see [`crate::config::Config::word_length_budgets`].
+//! Every other rule is met by [`crate::password_maker::PasswordMaker`] throwing away
+//! passwords that break it and making new ones.
+//! Throwing passwords away makes the ones that are kept more predictable,
+//! see [`crate::entropy::Entropy::policy`].
+use crate::types::CharacterClass;
+
+/// How many passwords are made to estimate how often the policy is met.
+pub const SAMPLES: usize = 1000;
+/// The seed for the passwords made to estimate how often the policy is met,
+/// fixed so that the estimate is the same every time.
+pub const SAMPLE_SEED: u64 = 0;
+
+/// Rules that every password must follow.
+///
+/// The default policy has no rules.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+    /// if not 0, the most characters a password may have
+    pub max_length: u8,
+    /// kinds of characters that must each appear at least once
+    pub required_characters: Vec<CharacterClass>,
+    /// if not 0, the most times a character may be repeated in a row
+    pub max_repeated: u8,
+    /// characters that must not appear
+    pub banned_characters: Vec<char>,
+}
+
+impl Policy {
+    /// Whether every password meets this policy.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    /// Whether `password` meets this policy.
+    pub fn allows(&self, password: &str) -> bool {
+        (self.max_length == 0 || password.chars().count() <= self.max_length as usize)
+            && self
+                .required_characters
+                .iter()
+                .all(|class| password.chars().any(|c| is_in_class(*class, c)))
+            && (self.max_repeated == 0 || longest_run(password) <= self.max_repeated as usize)
+            && !password.contains(self.banned_characters.as_slice())
+    }
+}
+
+/// Whether `c` is of the kind described by `class`.
+fn is_in_class(class: CharacterClass, c: char) -> bool {
+    match class {
+        CharacterClass::Upper => c.is_uppercase(),
+        CharacterClass::Lower => c.is_lowercase(),
+        CharacterClass::Digit => c.is_ascii_digit(),
+        CharacterClass::Symbol => !c.is_alphanumeric(),
+    }
+}
+
+/// The most times any character is repeated in a row.
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev = None;
+    for c in password.chars() {
+        run = if prev == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        prev = Some(c);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_policy_allows_anything() {
+        let policy = Policy::default();
+        assert!(policy.is_empty());
+        assert!(policy.allows(""));
+        assert!(policy.allows("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"));
+    }
+
+    #[test]
+    fn test_max_length() {
+        let policy = Policy {
+            max_length: 5,
+            ..Policy::default()
+        };
+        assert!(policy.allows("héllo"));
+        assert!(!policy.allows("hello!"));
+    }
+
+    #[test]
+    fn test_required_characters() {
+        let policy = Policy {
+            required_characters: vec![
+                CharacterClass::Upper,
+                CharacterClass::Lower,
+                CharacterClass::Digit,
+                CharacterClass::Symbol,
+            ],
+            ..Policy::default()
+        };
+        assert!(policy.allows("Correct horse 1"));
+        assert!(!policy.allows("correct horse 1"));
+        assert!(!policy.allows("CORRECT HORSE 1"));
+        assert!(!policy.allows("Correct horse one"));
+        assert!(!policy.allows("CorrectHorse1"));
+    }
+
+    #[test]
+    fn test_max_repeated() {
+        let policy = Policy {
+            max_repeated: 2,
+            ..Policy::default()
+        };
+        assert_eq!(longest_run(""), 0);
+        assert_eq!(longest_run("abbcccb"), 3);
+        assert!(policy.allows("!hello!world!"));
+        assert!(!policy.allows("!!!hello"));
+    }
+
+    #[test]
+    fn test_banned_characters() {
+        let policy = Policy {
+            banned_characters: vec!['l', '1'],
+            ..Policy::default()
+        };
+        assert!(policy.allows("WORLD"));
+        assert!(!policy.allows("world"));
+        assert!(!policy.allows("21"));
+    }
+}
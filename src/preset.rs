@@ -0,0 +1,384 @@
+//! Named configurations.
+//!
+//! The built-in presets are modelled on the ones that ship with Crypt::HSXKPasswd,
+//! see [https://metacpan.org/pod/Crypt::HSXKPasswd#PRESETS](https://metacpan.org/pod/Crypt::HSXKPasswd#PRESETS).
+//!
+//! User presets are stored in a [`PresetStore`], one config file per preset.
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::config::ConfigBuilder;
+use crate::config_file::config_dir;
+use crate::config_file::parse_config;
+use crate::config_file::read_config_file;
+use crate::config_file::write_config;
+use crate::types::Preset;
+use crate::types::StrEnum;
+use crate::types::ValidationError;
+
+/// The name of the directory, inside [`config_dir`], that holds user presets.
+pub const PRESETS_DIR_NAME: &str = "presets";
+/// The extension of user preset files.
+const PRESET_FILE_EXTENSION: &str = "toml";
+
+/// Padding characters that are safe to use on most websites.
+const WEB_PADDING_CHARACTERS: &str = "!@$%^&*+=:|~?";
+/// Separator characters that are safe to use on most websites.
+const WEB_SEPARATOR_CHARACTERS: &str = "-+=.*_|~,";
+
+impl Preset {
+    /// The settings that make up this preset.
+    ///
+    /// Settings the preset does not mention are left unset,
+    /// so that they can come from elsewhere or fall back to the defaults.
+    pub fn config_builder(self) -> ConfigBuilder {
+        let s = |value: &str| Some(value.to_owned());
+        let builder = ConfigBuilder::new();
+        match self {
+            Self::Default => builder,
+            Self::Web32 => builder
+                .word_count(s("4"))
+                .word_min_length(s("4"))
+                .word_max_length(s("5"))
+                .word_transformation(s("alternating-lower-upper"))
+                .digits_before(s("2"))
+                .digits_after(s("2"))
+                .padding_type(s("fixed"))
+                .padding_length(s("1"))
+                .padding_characters(s(WEB_PADDING_CHARACTERS))
+                .separator_characters(s(WEB_SEPARATOR_CHARACTERS)),
+            Self::Web16 => builder
+                .word_count(s("3"))
+                .word_min_length(s("4"))
+                .word_max_length(s("4"))
+                .word_transformation(s("random-upper-lower"))
+                .digits_before(s("0"))
+                .digits_after(s("1"))
+                .padding_type(s("none"))
+                .separator_characters(s("!@$%^&*-_+=:|~?/.;")),
+            Self::Wifi => builder
+                .word_count(s("6"))
+                .word_min_length(s("4"))
+                .word_max_length(s("8"))
+                .word_transformation(s("random-upper-lower"))
+                .digits_before(s("4"))
+                .digits_after(s("4"))
+                .padding_type(s("adaptive"))
+                .padding_length(s("63"))
+                .padding_characters(s(WEB_PADDING_CHARACTERS))
+                .separator_characters(s(WEB_SEPARATOR_CHARACTERS)),
+            Self::AppleId => builder
+                .word_count(s("3"))
+                .word_min_length(s("4"))
+                .word_max_length(s("7"))
+                .word_transformation(s("random-upper-lower"))
+                .digits_before(s("2"))
+                .digits_after(s("2"))
+                .padding_type(s("fixed"))
+                .padding_length(s("1"))
+                .padding_characters(s("-:.!?@&"))
+                .separator_characters(s("-:.@, "))
+                .required_characters(s("upper,lower,digit")),
+            Self::Ntlm => builder
+                .word_count(s("2"))
+                .word_min_length(s("5"))
+                .word_max_length(s("5"))
+                .word_transformation(s("capitalize-not-first"))
+                .digits_before(s("1"))
+                .digits_after(s("0"))
+                // 13 characters unpadded, so exactly one padding character is appended
+                .padding_type(s("adaptive"))
+                .padding_length(s("14"))
+                .padding_characters(s(WEB_PADDING_CHARACTERS))
+                .separator_characters(s(WEB_SEPARATOR_CHARACTERS)),
+            Self::SecurityQ => builder
+                .word_count(s("6"))
+                .word_min_length(s("4"))
+                .word_max_length(s("8"))
+                .word_transformation(s("none"))
+                .digits_before(s("0"))
+                .digits_after(s("0"))
+                .padding_type(s("none"))
+                .separator_characters(s(" ")),
+            Self::Xkcd => builder
+                .word_count(s("4"))
+                .word_min_length(s("4"))
+                .word_max_length(s("8"))
+                .word_transformation(s("random-upper-lower"))
+                .digits_before(s("0"))
+                .digits_after(s("0"))
+                .padding_type(s("none"))
+                .separator_characters(s("-")),
+        }
+    }
+}
+
+/// A directory of user presets.
+///
+/// Each preset is a config file named `NAME.toml` which sets every [`Config`] value.
+#[derive(Debug, Clone)]
+pub struct PresetStore {
+    /// where the preset files are kept
+    pub dir: PathBuf,
+}
+
+impl PresetStore {
+    /// Use the presets in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+    /// Use the `presets` directory inside [`config_dir`].
+    pub fn open_default() -> Result<Self, ValidationError> {
+        config_dir()
+            .map(|dir| Self::new(dir.join(PRESETS_DIR_NAME)))
+            .ok_or_else(|| ValidationError::Message("unable to find a config directory".to_owned()))
+    }
+    /// The file that the preset `name` is stored in.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.{PRESET_FILE_EXTENSION}"))
+    }
+    /// The names of every stored preset, sorted.
+    ///
+    /// A missing directory is not an error and results in no presets.
+    pub fn list(&self) -> Result<Vec<String>, ValidationError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&self.dir, &e)),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(&self.dir, &e))?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == PRESET_FILE_EXTENSION)
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+                && validate_name(name).is_ok()
+            {
+                names.push(name.to_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+    /// Load the preset `name`.
+    pub fn load(&self, name: &str) -> Result<ConfigBuilder, ValidationError> {
+        validate_name(name)?;
+        read_config_file(self.path(name))
+    }
+    /// The contents of the preset file `name`.
+    pub fn show(&self, name: &str) -> Result<String, ValidationError> {
+        validate_name(name)?;
+        let path = self.path(name);
+        fs::read_to_string(&path).map_err(|e| io_error(&path, &e))
+    }
+    /// Store every value of `config` as the preset `name`, replacing any existing preset.
+    ///
+    /// The preset is read back the way [`PresetStore::load`] reads it before it is stored,
+    /// so that a preset which saves can always be loaded.
+    /// Returns the path of the preset file.
+    pub fn save(&self, name: &str, config: &Config) -> Result<PathBuf, ValidationError> {
+        validate_name(name)?;
+        if Preset::to_member(name).is_ok() {
+            return Err(ValidationError::Message(format!(
+                "`{name}` is a built-in preset and cannot be replaced"
+            )));
+        }
+        fs::create_dir_all(&self.dir).map_err(|e| io_error(&self.dir, &e))?;
+        let path = self.path(name);
+        let contents = write_config(&ConfigBuilder::from(config));
+        if parse_config(&contents)?.build()? != ConfigBuilder::from(config).build()? {
+            return Err(ValidationError::Message(format!(
+                "preset `{name}` would not load back the same settings"
+            )));
+        }
+        fs::write(&path, contents).map_err(|e| io_error(&path, &e))?;
+        Ok(path)
+    }
+    /// Delete the preset `name`.
+    pub fn delete(&self, name: &str) -> Result<(), ValidationError> {
+        validate_name(name)?;
+        let path = self.path(name);
+        fs::remove_file(&path).map_err(|e| io_error(&path, &e))
+    }
+    /// Find a preset by name, checking the built-in presets before the stored ones.
+    pub fn resolve(&self, name: &str) -> Result<ConfigBuilder, ValidationError> {
+        let name = name.to_ascii_lowercase();
+        if let Ok(preset) = Preset::to_member(&name) {
+            return Ok(preset.config_builder());
+        }
+        let names = self.list()?;
+        if !names.contains(&name) {
+            let mut choices: Vec<&str> = Preset::NAME_MEMBER_ARR
+                .iter()
+                .map(|(name, _)| *name)
+                .collect();
+            choices.extend(names.iter().map(String::as_str));
+            return Err(ValidationError::Message(format!(
+                "`{name}` is not a known preset. Possible choices: {}",
+                choices.join(", ")
+            )));
+        }
+        self.load(&name)
+    }
+}
+
+/// Preset names become file names, so they are restricted to lowercase ASCII
+/// letters, digits, `-` and `_`.
+fn validate_name(name: &str) -> Result<(), ValidationError> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if name.is_empty() || !valid {
+        return Err(ValidationError::Message(format!(
+            "invalid preset name `{name}`, use lowercase letters, digits, `-` and `_`"
+        )));
+    }
+    Ok(())
+}
+
+/// Describe an I/O error along with the path it happened on.
+fn io_error(path: &Path, e: &io::Error) -> ValidationError {
+    ValidationError::Context(
+        path.display().to_string(),
+        Box::new(ValidationError::Message(e.to_string())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    /// Generate a batch of passwords with a preset.
+    fn make_passwords(preset: Preset) -> Vec<String> {
+        let mut maker = make_seeded_maker_big_list(1);
+        maker.config = preset.config_builder().build().unwrap();
+        maker.config.count = 255;
+        maker.make_passwords()
+    }
+
+    #[test]
+    fn test_presets_build() {
+        for (name, preset) in Preset::into_iter() {
+            assert!(preset.config_builder().build().is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_default_preset_is_default_config() {
+        assert_eq!(
+            Preset::Default.config_builder().build().unwrap(),
+            ConfigBuilder::new().build().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_preset_length_limits() {
+        let limits = [
+            (Preset::Web16, 16),
+            (Preset::Web32, 32),
+            (Preset::Ntlm, 14),
+            (Preset::Wifi, 63),
+        ];
+        for (preset, limit) in limits {
+            for password in make_passwords(preset) {
+                assert!(password.chars().count() <= limit, "{preset}: {password}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_preset_exact_lengths() {
+        for password in make_passwords(Preset::Ntlm) {
+            assert_eq!(password.chars().count(), 14, "{password}");
+        }
+        for password in make_passwords(Preset::Wifi) {
+            assert_eq!(password.chars().count(), 63, "{password}");
+        }
+    }
+
+    /// A preset store in a fresh temporary directory.
+    fn make_store(name: &str) -> PresetStore {
+        let dir = std::env::temp_dir().join(format!(
+            "fmn-passgen-test-presets-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        PresetStore::new(dir)
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let store = make_store("round-trip");
+        assert_eq!(store.list().unwrap(), Vec::<String>::new());
+        let config = ConfigBuilder::new()
+            .word_count(Some("6".to_owned()))
+            .padding_characters(Some("\"'#".to_owned()))
+            .separator_characters(Some(" ".to_owned()))
+            .rng_type(Some("csprng".to_owned()))
+            .build()
+            .unwrap();
+        store.save("vpn", &config).unwrap();
+        store.save("db-root", &config).unwrap();
+        assert_eq!(store.list().unwrap(), ["db-root", "vpn"]);
+        assert_eq!(store.load("vpn").unwrap().build().unwrap(), config);
+        assert_eq!(store.resolve("VPN").unwrap().build().unwrap(), config);
+        assert!(store.show("vpn").unwrap().contains("rng_type = \"csprng\""));
+        store.delete("vpn").unwrap();
+        assert_eq!(store.list().unwrap(), ["db-root"]);
+        assert!(store.load("vpn").is_err());
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn test_store_round_trip_cross_field_settings() {
+        let store = make_store("cross-field");
+        let configs = [
+            ConfigBuilder::new()
+                .word_count(Some("2".to_owned()))
+                .max_length(Some("15".to_owned())),
+            ConfigBuilder::new()
+                .word_min_length(Some("1".to_owned()))
+                .word_max_length(Some("2".to_owned())),
+        ];
+        for builder in configs {
+            let config = builder.build().unwrap();
+            store.save("short", &config).unwrap();
+            // the default character lists come back sorted
+            let expected = ConfigBuilder::from(&config).build().unwrap();
+            assert_eq!(store.load("short").unwrap().build().unwrap(), expected);
+            let loaded = store.resolve("short").unwrap().build().unwrap();
+            assert_eq!(loaded.word_count, config.word_count);
+            assert_eq!(loaded.word_min_length, config.word_min_length);
+            assert_eq!(loaded.word_max_length, config.word_max_length);
+            assert_eq!(loaded.policy, config.policy);
+        }
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn test_store_resolves_built_in_first() {
+        let store = make_store("built-in");
+        assert_eq!(
+            store.resolve("xkcd").unwrap().build().unwrap(),
+            Preset::Xkcd.config_builder().build().unwrap()
+        );
+        let config = ConfigBuilder::new().build().unwrap();
+        assert!(store.save("xkcd", &config).is_err());
+        assert!(store.resolve("missing").is_err());
+    }
+
+    #[test]
+    fn test_store_rejects_bad_names() {
+        let store = make_store("bad-names");
+        let config = ConfigBuilder::new().build().unwrap();
+        for name in ["", "../escape", "with space", "Upper", "a.b"] {
+            assert!(store.save(name, &config).is_err(), "{name}");
+        }
+        assert!(!store.dir.exists());
+    }
+}
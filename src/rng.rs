@@ -0,0 +1,364 @@
+//! Random number generators that [`crate::password_maker::PasswordMaker`] can use.
+//!
+//! Every random choice made while creating a password is either a uniform choice of index
+//! or a coin flip, see [`PasswordRng`].
+//! Software RNGs use the default implementations, which match [`rand::seq::IndexedRandom::choose`].
+//! [`DiceRng`] instead asks for the fewest physical dice rolls that can make each choice.
+#[cfg(feature = "dice")]
+use std::fmt;
+#[cfg(feature = "dice")]
+use std::io;
+#[cfg(feature = "dice")]
+use std::io::BufRead;
+#[cfg(feature = "dice")]
+use std::io::IsTerminal;
+#[cfg(feature = "dice")]
+use std::io::Write;
+
+use rand::Rng;
+use rand::TryRngCore;
+use rand::rngs::OsRng;
+use rand::rngs::SmallRng;
+use rand::rngs::StdRng;
+use rand::rngs::ThreadRng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "chacha")]
+use rand_chacha::ChaCha20Rng;
+
+/// A source of the random choices that make up a password.
+pub trait PasswordRng: TryRngCore {
+    /// Choose an index below `n` uniformly at random.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    fn random_index(&mut self, n: usize) -> usize {
+        self.unwrap_mut().random_range(..n)
+    }
+    /// Flip a fair coin.
+    fn random_bool(&mut self) -> bool {
+        self.unwrap_mut().random()
+    }
+    /// Take the error that stopped the choices since the last call from being random, if any.
+    ///
+    /// The choices above cannot report errors, so a source that can fail, such as [`DiceRng`],
+    /// keeps its first error for this and makes arbitrary choices until it is taken.
+    fn take_error(&mut self) -> Option<Self::Error> {
+        None
+    }
+}
+
+/// The random choices of a [`PasswordRng`], in a form that can be used as a trait object,
+/// see [`crate::word_transformer::WordTransformer`].
+pub trait RandomChoice {
+    /// Choose an index below `n` uniformly at random, see [`PasswordRng::random_index`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    fn choose_index(&mut self, n: usize) -> usize;
+    /// Flip a fair coin, see [`PasswordRng::random_bool`].
+    fn flip_coin(&mut self) -> bool;
+}
+
+impl<T: PasswordRng> RandomChoice for T {
+    fn choose_index(&mut self, n: usize) -> usize {
+        self.random_index(n)
+    }
+    fn flip_coin(&mut self) -> bool {
+        self.random_bool()
+    }
+}
+
+impl PasswordRng for OsRng {}
+impl PasswordRng for ThreadRng {}
+impl PasswordRng for SmallRng {}
+impl PasswordRng for StdRng {}
+#[cfg(feature = "chacha")]
+impl PasswordRng for ChaCha8Rng {}
+#[cfg(feature = "chacha")]
+impl PasswordRng for ChaCha12Rng {}
+#[cfg(feature = "chacha")]
+impl PasswordRng for ChaCha20Rng {}
+
+/// Choose an item from `items` uniformly at random, or [`None`] if it is empty.
+pub fn choose<'a, T: PasswordRng, U>(rng: &mut T, items: &'a [U]) -> Option<&'a U> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(&items[rng.random_index(items.len())])
+    }
+}
+
+/// Number of faces on a die.
+#[cfg(feature = "dice")]
+const DICE_FACES: u64 = 6;
+
+/// Random choices made by rolling physical dice.
+///
+/// To choose one of `n` options, the fewest dice that have at least `n` outcomes are rolled and
+/// read as a base 6 number, the first die being the most significant.
+/// Outcomes past the largest multiple of `n` are rejected and rolled again, so every option is
+/// equally likely.
+/// For the EFF large wordlist that is 5 dice per word, and the rolls match the dice indexes
+/// printed in the list.
+///
+/// Rolls are read from `input` as the digits 1 to 6, separated by whitespace or not at all.
+/// Rolls left over from a line are used for the next choice,
+/// so every roll can be provided up front by piping them in.
+/// If `prompt` is set, the number of dice needed is written to it before each read,
+/// and lines with anything other than rolls are ignored rather than treated as an error.
+#[cfg(feature = "dice")]
+pub struct DiceRng {
+    input: Box<dyn BufRead>,
+    prompt: Option<Box<dyn Write>>,
+    /// rolls that have been read but not used yet, as values from 0 to 5
+    rolls: Vec<u64>,
+    /// the first error since [`PasswordRng::take_error`] was last called
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "dice")]
+impl fmt::Debug for DiceRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiceRng")
+            .field("prompt", &self.prompt.is_some())
+            .field("rolls", &self.rolls)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read rolls from stdin, prompting on stderr if stdin is a terminal.
+#[cfg(feature = "dice")]
+impl Default for DiceRng {
+    fn default() -> Self {
+        let stdin = io::stdin();
+        let prompt: Option<Box<dyn Write>> = if stdin.is_terminal() {
+            Some(Box::new(io::stderr()))
+        } else {
+            None
+        };
+        Self::new(Box::new(stdin.lock()), prompt)
+    }
+}
+
+#[cfg(feature = "dice")]
+impl DiceRng {
+    /// Read rolls from `input`, writing prompts to `prompt` if given.
+    pub fn new(input: Box<dyn BufRead>, prompt: Option<Box<dyn Write>>) -> Self {
+        Self {
+            input,
+            prompt,
+            rolls: Vec::new(),
+            error: None,
+        }
+    }
+    /// Choose a number below `n` uniformly at random, by rolling as few dice as possible.
+    pub fn roll_below(&mut self, n: u64) -> io::Result<u64> {
+        assert!(n > 0, "cannot choose from no options");
+        // 6^25 is the most outcomes that could be needed, which does not fit in a u64
+        let n = u128::from(n);
+        let mut dice = 0;
+        let mut outcomes: u128 = 1;
+        while outcomes < n {
+            dice += 1;
+            outcomes *= u128::from(DICE_FACES);
+        }
+        let limit = outcomes - outcomes % n;
+        loop {
+            let value = self.take_rolls(dice)?.into_iter().fold(0, |acc, roll| {
+                acc * u128::from(DICE_FACES) + u128::from(roll)
+            });
+            if value < limit {
+                #[expect(
+                    clippy::cast_possible_truncation,
+                    reason = "the result is below `n`, which came from a u64"
+                )]
+                return Ok((value % n) as u64);
+            }
+            self.write_prompt("that roll would favour some choices, roll again\n")?;
+        }
+    }
+    /// Take `count` rolls, reading more input as needed.
+    fn take_rolls(&mut self, count: usize) -> io::Result<Vec<u64>> {
+        while self.rolls.len() < count {
+            let needed = count - self.rolls.len();
+            self.write_prompt(&format!(
+                "roll {needed} {}: ",
+                if needed == 1 { "die" } else { "dice" }
+            ))?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "ran out of dice rolls",
+                ));
+            }
+            match parse_rolls(&line) {
+                Some(rolls) => self.rolls.extend(rolls),
+                None if self.prompt.is_some() => {
+                    self.write_prompt("dice rolls must be the digits 1 to 6\n")?;
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("`{}` is not a list of dice rolls", line.trim()),
+                    ));
+                }
+            }
+        }
+        Ok(self.rolls.drain(..count).collect())
+    }
+    /// Write to the prompt, if there is one.
+    fn write_prompt(&mut self, msg: &str) -> io::Result<()> {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.write_all(msg.as_bytes())?;
+            prompt.flush()?;
+        }
+        Ok(())
+    }
+    /// Make a choice, or keep the error for [`PasswordRng::take_error`] if no more rolls can be read.
+    ///
+    /// Once there is an error, no more rolls are asked for and every choice is 0.
+    fn roll_or_keep_error(&mut self, n: u64) -> u64 {
+        if self.error.is_some() {
+            return 0;
+        }
+        self.roll_below(n).unwrap_or_else(|e| {
+            self.error = Some(e);
+            0
+        })
+    }
+}
+
+/// Read a line of rolls as values from 0 to 5.
+#[cfg(feature = "dice")]
+fn parse_rolls(line: &str) -> Option<Vec<u64>> {
+    line.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '1'..='6' => Some(u64::from(c) - u64::from('1')),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Raw random numbers are made from dice rolls too, 13 dice for every 32 bits.
+#[cfg(feature = "dice")]
+impl TryRngCore for DiceRng {
+    type Error = io::Error;
+
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "the result is always below 2^32"
+    )]
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        self.roll_below(1 << 32).map(|value| value as u32)
+    }
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        let high = u64::from(self.try_next_u32()?);
+        let low = u64::from(self.try_next_u32()?);
+        Ok(high << 32 | low)
+    }
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        for chunk in dst.chunks_mut(4) {
+            let bytes = self.try_next_u32()?.to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "dice")]
+impl PasswordRng for DiceRng {
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "the result is below `n`, which is a usize"
+    )]
+    fn random_index(&mut self, n: usize) -> usize {
+        self.roll_or_keep_error(n as u64) as usize
+    }
+    fn random_bool(&mut self) -> bool {
+        self.roll_or_keep_error(2) == 1
+    }
+    fn take_error(&mut self) -> Option<Self::Error> {
+        self.error.take()
+    }
+}
+
+#[cfg(all(test, feature = "dice"))]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Makes a [`DiceRng`] that reads `rolls` without prompting.
+    fn make_dice(rolls: &str) -> DiceRng {
+        DiceRng::new(Box::new(Cursor::new(rolls.to_owned())), None)
+    }
+
+    #[test]
+    fn test_roll_below_eff_large() {
+        let mut dice = make_dice("11111\n6 6 6 6 6\n12345");
+        assert_eq!(dice.roll_below(7776).unwrap(), 0);
+        assert_eq!(dice.roll_below(7776).unwrap(), 7775);
+        // 1-2-3-4-5 is 0 * 1296 + 1 * 216 + 2 * 36 + 3 * 6 + 4
+        assert_eq!(dice.roll_below(7776).unwrap(), 310);
+    }
+
+    #[test]
+    fn test_roll_below_rejects_biased_rolls() {
+        // 10 options need 2 dice, the 36 outcomes are cut down to 30
+        // 6-1 is 30 and must be rolled again, 3-4 is 15
+        let mut dice = make_dice("61 34");
+        assert_eq!(dice.roll_below(10).unwrap(), 5);
+        assert!(dice.rolls.is_empty());
+    }
+
+    #[test]
+    fn test_roll_below_one_option_needs_no_rolls() {
+        let mut dice = make_dice("");
+        assert_eq!(dice.roll_below(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_rolls_carry_over_lines() {
+        let mut dice = make_dice("1\n1 1\n1 12\n");
+        assert_eq!(dice.roll_below(7776).unwrap(), 0);
+        assert_eq!(dice.roll_below(6).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_invalid_rolls() {
+        assert_eq!(
+            make_dice("1 2 7").roll_below(6).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            make_dice("1").roll_below(36).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_invalid_rolls_prompt_again() {
+        let mut dice = DiceRng::new(
+            Box::new(Cursor::new("oops\n4\n".to_owned())),
+            Some(Box::new(io::sink())),
+        );
+        assert_eq!(dice.roll_below(6).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_random_bool() {
+        let mut dice = make_dice("1 2 3 4");
+        assert!(!dice.random_bool());
+        assert!(dice.random_bool());
+        assert!(!dice.random_bool());
+        assert!(dice.random_bool());
+    }
+}
@@ -0,0 +1,154 @@
+//! Derive site passwords from a master passphrase.
+//!
+//! The master passphrase, site name and counter are stretched with Argon2id into a seed for
+//! [`ChaCha20Rng`], which then drives the usual [`PasswordMaker`] pipeline.
+//! The same inputs, [`Config`] and wordlist always make the same passwords,
+//! so site passwords can be made again instead of being stored.
+//!
+//! Every constant here is part of the derivation, changing any of them changes every password.
+use argon2::Algorithm;
+use argon2::Argon2;
+use argon2::Params;
+use argon2::Version;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::Config;
+use crate::password_maker::PasswordMaker;
+use crate::types::ValidationError;
+
+/// Keeps our salts apart from those of any other program using the same passphrase.
+const SALT_PREFIX: &[u8] = b"fmn-passgen site v1";
+/// Argon2id memory cost in KiB.
+const MEMORY_KIB: u32 = 64 * 1024;
+/// Argon2id passes over memory.
+const ITERATIONS: u32 = 3;
+/// Argon2id lanes.
+const PARALLELISM: u32 = 1;
+
+/// Site names are compared case insensitively and ignore surrounding whitespace,
+/// so `Example.com` and `example.com ` are the same site.
+/// Like the passphrase, they are normalized to NFC, see [`normalize_master`].
+fn normalize_site(site: &str) -> String {
+    site.trim().nfc().collect::<String>().to_lowercase()
+}
+
+/// The same passphrase can be typed as composed or decomposed characters, such as `é` or `e` and `´`,
+/// depending on the keyboard and platform, so it is normalized to NFC before hashing.
+fn normalize_master(master: &str) -> String {
+    master.nfc().collect()
+}
+
+/// Stretch the inputs into a 32 byte seed with Argon2id.
+///
+/// The salt is made from the site name and counter, each site name being length prefixed
+/// so that no two different inputs share a salt.
+/// The passphrase and site name are normalized to NFC first.
+pub fn derive_seed(master: &str, site: &str, counter: u32) -> Result<[u8; 32], ValidationError> {
+    if master.is_empty() {
+        return Err(ValidationError::Message(
+            "the master passphrase must not be empty".to_owned(),
+        ));
+    }
+    let master = normalize_master(master);
+    let site = normalize_site(site);
+    if site.is_empty() {
+        return Err(ValidationError::Message(
+            "the site name must not be empty".to_owned(),
+        ));
+    }
+    let site_len = u32::try_from(site.len())
+        .map_err(|_| ValidationError::Message("the site name is too long".to_owned()))?;
+    let mut salt = SALT_PREFIX.to_vec();
+    salt.extend(site_len.to_be_bytes());
+    salt.extend(site.as_bytes());
+    salt.extend(counter.to_be_bytes());
+
+    let argon2_error = |e: argon2::Error| ValidationError::Message(format!("argon2: {e}"));
+    let mut seed = [0; 32];
+    let params =
+        Params::new(MEMORY_KIB, ITERATIONS, PARALLELISM, Some(seed.len())).map_err(argon2_error)?;
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(master.as_bytes(), &salt, &mut seed)
+        .map_err(argon2_error)?;
+    Ok(seed)
+}
+
+impl PasswordMaker<ChaCha20Rng> {
+    /// Create a [`PasswordMaker`] whose passwords are derived from a master passphrase,
+    /// site name and counter, see [`derive_seed`].
+    ///
+    /// Increment the counter to change a site's password.
+    pub fn for_site(
+        config: Config,
+        wordlist: Vec<String>,
+        master: &str,
+        site: &str,
+        counter: u32,
+    ) -> Result<Self, ValidationError> {
+        let seed = derive_seed(master, site, counter)?;
+        Ok(Self::from_rng(
+            ChaCha20Rng::from_seed(seed),
+            config,
+            wordlist,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+    use crate::password_maker::default_wordlist;
+
+    fn make_password(master: &str, site: &str, counter: u32) -> String {
+        let config = ConfigBuilder::new().build().unwrap();
+        PasswordMaker::for_site(config, default_wordlist(), master, site, counter)
+            .unwrap()
+            .make_password()
+    }
+
+    /// Site passwords must never change between versions, or they cannot be made again.
+    #[test]
+    fn test_site_password_is_stable() {
+        assert_eq!(
+            make_password("correct horse battery staple", "example.com", 1),
+            make_password("correct horse battery staple", " Example.COM ", 1),
+        );
+        assert_eq!(
+            make_password("correct horse battery staple", "example.com", 1),
+            "/footman$SHADED$frame$DIVISIBLE$91/"
+        );
+    }
+
+    #[test]
+    fn test_site_password_inputs_matter() {
+        let password = make_password("master", "example.com", 1);
+        assert_ne!(password, make_password("master", "example.com", 2));
+        assert_ne!(password, make_password("master", "example.org", 1));
+        assert_ne!(password, make_password("Master", "example.com", 1));
+    }
+
+    #[test]
+    fn test_site_password_normalizes_unicode() {
+        // "café crème" with composed and with decomposed accents
+        let composed = "caf\u{e9} cr\u{e8}me";
+        let decomposed = "cafe\u{301} cre\u{300}me";
+        assert_ne!(composed, decomposed);
+        assert_eq!(
+            derive_seed(composed, "example.com", 1).unwrap(),
+            derive_seed(decomposed, "example.com", 1).unwrap()
+        );
+        assert_eq!(
+            derive_seed("master", composed, 1).unwrap(),
+            derive_seed("master", decomposed, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_derive_seed_empty_inputs() {
+        assert!(derive_seed("", "example.com", 1).is_err());
+        assert!(derive_seed("master", " ", 1).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Describe the shape of a password with a template, such as `{Word}{sep}{word}{sep}{dd}{sym}{WORD}`.
+//!
+//! A template is parsed into a sequence of [`Token`]s, which
+//! [`crate::password_maker::PasswordMaker`] turns into a password one token at a time.
+//! Words, digits, symbols, separators and padding are chosen just as they are without a template,
+//! using the wordlist filter, [`crate::word_transformer`], and the configured alphabets.
+//! Words go through [`crate::config::Config::word_transformation`] before their token sets the case.
+//! Anything outside braces is copied into the password as is, with `{{` and `}}` standing for braces.
+use std::fmt;
+
+use crate::types::ValidationError;
+
+/// Every token that can appear between braces, with what it stands for.
+pub const TOKENS: [(&str, &str); 7] = [
+    ("{word}", "a lowercase word"),
+    ("{Word}", "a capitalized word"),
+    ("{WORD}", "an uppercase word"),
+    ("{d}", "a digit, repeat the d for more, as in {ddd}"),
+    ("{sym}", "a symbol-character"),
+    (
+        "{sep}",
+        "a separator, chosen as the separator-type describes",
+    ),
+    (
+        "{pad}",
+        "a padding-character, chosen as the padding-mode describes",
+    ),
+];
+
+/// One step in making a password from a template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// `{word}`
+    LowerWord,
+    /// `{Word}`
+    CapitalizedWord,
+    /// `{WORD}`
+    UpperWord,
+    /// `{d}`, `{dd}` and so on, holding the number of digits
+    Digits(usize),
+    /// `{sym}`
+    Symbol,
+    /// `{sep}`
+    Separator,
+    /// `{pad}`
+    Padding,
+    /// text copied into the password as is
+    Literal(String),
+}
+
+/// A parsed template, which remembers the text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    source: String,
+    tokens: Vec<Token>,
+}
+
+impl Template {
+    /// Parse a template, pointing at the first token that is not valid.
+    ///
+    /// Positions in errors count characters from 1, as a text editor would.
+    pub fn parse(source: &str) -> Result<Self, ValidationError> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().enumerate().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().is_some_and(|(_, next)| *next == '{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().is_some_and(|(_, next)| *next == '}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    let token = if closed { parse_token(&name) } else { None };
+                    let Some(token) = token else {
+                        let text = if closed {
+                            format!("{{{name}}}")
+                        } else {
+                            format!("{{{name}")
+                        };
+                        return Err(ValidationError::InvalidTemplateToken(text, i + 1));
+                    };
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(token);
+                }
+                '}' => {
+                    return Err(ValidationError::InvalidTemplateToken("}".to_owned(), i + 1));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+        Ok(Self {
+            source: source.to_owned(),
+            tokens,
+        })
+    }
+    /// The tokens, in order.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+    /// How many of the tokens match `predicate`.
+    pub fn count(&self, predicate: impl Fn(&Token) -> bool) -> usize {
+        self.tokens.iter().filter(|token| predicate(token)).count()
+    }
+    /// How many words the template has.
+    pub fn word_count(&self) -> usize {
+        self.count(Token::is_word)
+    }
+    /// How many digits the template has.
+    pub fn digit_count(&self) -> usize {
+        self.tokens
+            .iter()
+            .map(|token| match token {
+                Token::Digits(n) => *n,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl Token {
+    /// Whether the token is replaced by a word.
+    pub fn is_word(&self) -> bool {
+        matches!(
+            self,
+            Self::LowerWord | Self::CapitalizedWord | Self::UpperWord
+        )
+    }
+}
+
+/// The token named between a pair of braces, if there is one.
+fn parse_token(name: &str) -> Option<Token> {
+    match name {
+        "word" => Some(Token::LowerWord),
+        "Word" => Some(Token::CapitalizedWord),
+        "WORD" => Some(Token::UpperWord),
+        "sym" => Some(Token::Symbol),
+        "sep" => Some(Token::Separator),
+        "pad" => Some(Token::Padding),
+        d if !d.is_empty() && d.chars().all(|c| c == 'd') => Some(Token::Digits(d.len())),
+        _ => None,
+    }
+}
+
+/// Displays the text the template was parsed from.
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let template = Template::parse("{Word}{sep}{word}{sep}{dd}{sym}{WORD}").unwrap();
+        assert_eq!(
+            template.tokens(),
+            [
+                Token::CapitalizedWord,
+                Token::Separator,
+                Token::LowerWord,
+                Token::Separator,
+                Token::Digits(2),
+                Token::Symbol,
+                Token::UpperWord,
+            ]
+        );
+        assert_eq!(template.word_count(), 3);
+        assert_eq!(template.digit_count(), 2);
+        assert_eq!(template.count(|token| *token == Token::Separator), 2);
+        assert_eq!(
+            template.to_string(),
+            "{Word}{sep}{word}{sep}{dd}{sym}{WORD}"
+        );
+    }
+
+    #[test]
+    fn test_parse_literals() {
+        let template = Template::parse("pin: {{{ddd}}} ok").unwrap();
+        assert_eq!(
+            template.tokens(),
+            [
+                Token::Literal("pin: {".to_owned()),
+                Token::Digits(3),
+                Token::Literal("} ok".to_owned()),
+            ]
+        );
+        assert_eq!(Template::parse("").unwrap().tokens(), []);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |template: &str| Template::parse(template).unwrap_err().to_string();
+        assert_eq!(
+            error("{word}-{wrod}"),
+            "`{wrod}` at character 8 of the template is not a valid token"
+        );
+        assert_eq!(
+            error("{word}-{sep"),
+            "`{sep` at character 8 of the template is not a valid token"
+        );
+        assert_eq!(
+            error("{}"),
+            "`{}` at character 1 of the template is not a valid token"
+        );
+        assert_eq!(
+            error("a}b"),
+            "`}` at character 2 of the template is not a valid token"
+        );
+    }
+}
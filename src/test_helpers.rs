@@ -7,7 +7,6 @@
 include!(concat!(env!("OUT_DIR"), "/wordlist.rs"));
 
 use rand::SeedableRng;
-use rand::TryRngCore;
 use rand::rngs::SmallRng;
 
 use crate::config::ConfigBuilder;
@@ -15,22 +14,20 @@ use crate::password_maker::PasswordMaker;
 
 /// Makes a [`PasswordMaker`] with reproducible random output and a small wordlist.
 pub fn make_seeded_maker(seed: u64) -> PasswordMaker<SmallRng> {
-    let rng = SmallRng::seed_from_u64(seed).unwrap_err();
-    PasswordMaker {
-        rng,
-        config: ConfigBuilder::new().build().unwrap(),
-        wordlist: make_wordlist(),
-    }
+    PasswordMaker::from_rng(
+        SmallRng::seed_from_u64(seed),
+        ConfigBuilder::new().build().unwrap(),
+        make_wordlist(),
+    )
 }
 
 /// Makes a [`PasswordMaker`] with reproducible random output and a real wordlist.
 pub fn make_seeded_maker_big_list(seed: u64) -> PasswordMaker<SmallRng> {
-    let rng = SmallRng::seed_from_u64(seed).unwrap_err();
-    PasswordMaker {
-        rng,
-        config: ConfigBuilder::new().build().unwrap(),
-        wordlist: WORDLIST.iter().map(|s| String::from(*s)).collect(),
-    }
+    PasswordMaker::from_rng(
+        SmallRng::seed_from_u64(seed),
+        ConfigBuilder::new().build().unwrap(),
+        WORDLIST.iter().map(|s| String::from(*s)).collect(),
+    )
 }
 
 /// Makes a small, easy to work with wordlist.
@@ -24,6 +24,18 @@ impl Integer for MinimalSupportedInteger {}
 pub enum ValidationError {
     InvalidNumber(String, MinimalSupportedInteger, MinimalSupportedInteger),
     InvalidEnum(String),
+    /// the requested minimum entropy and the most entropy that can be reached
+    UnreachableEntropy(MinimalSupportedInteger, f64),
+    /// how many sample passwords were made, none of which met the [`crate::policy::Policy`]
+    UnsatisfiablePolicy(usize),
+    /// a token in a [`crate::template::Template`] that is not valid, and the character it starts at
+    InvalidTemplateToken(String, usize),
+    /// a setting name that does not exist, and the names that do
+    UnknownKey(String, &'static [&'static str]),
+    /// any other problem, such as a config file line that is not `key = value`
+    Message(String),
+    /// where the wrapped error came from, such as a file name or setting name
+    Context(String, Box<ValidationError>),
 }
 
 impl fmt::Display for ValidationError {
@@ -33,6 +45,25 @@ impl fmt::Display for ValidationError {
                 format!("`{value}` must be a positive integer between {min} and {max}")
             }
             Self::InvalidEnum(msg) => msg.clone(),
+            Self::UnreachableEntropy(target, max) => {
+                format!(
+                    "a minimum entropy of {target} bits is unreachable, at most {max:.1} bits are possible with these settings"
+                )
+            }
+            Self::UnsatisfiablePolicy(samples) => {
+                format!(
+                    "the password policy cannot be met with these settings, none of {samples} sample passwords met it"
+                )
+            }
+            Self::InvalidTemplateToken(token, position) => {
+                format!("`{token}` at character {position} of the template is not a valid token")
+            }
+            Self::UnknownKey(key, valid_choices) => {
+                let valid_choices = valid_choices.join(", ");
+                format!("`{key}` is not a valid setting. Possible choices: {valid_choices}")
+            }
+            Self::Message(msg) => msg.clone(),
+            Self::Context(context, e) => format!("{context}: {e}"),
         };
         write!(f, "{msg}")
     }
@@ -46,6 +77,7 @@ impl std::error::Error for ValidationError {}
 /// - Allows for referencing the kebab-case names of each enum member. (implemented by derive macro)
 /// - Allows for iterating over enum member, enum name pairs           (implemented by derive macro)
 /// - Allows for converting an enum member into it's kebab-case name.  (implemented by derive macro)
+/// - Allows for describing an enum member using it's doc comment.     (implemented by derive macro)
 /// - Allows for converting a &str to an enum member.                  (default implementation provided)
 ///
 /// Is used in conjunction with the [`StrEnum`] derive macro to provide
@@ -58,6 +90,7 @@ where
     const NAME_MEMBER_ARR: &[(&str, Self)];
     fn to_static_str(&self) -> &'static str;
     fn into_iter() -> impl Iterator<Item = &'static (&'static str, Self)>;
+    fn description(&self) -> &'static str;
     fn to_member(member: &str) -> Result<&Self, ValidationError> {
         Self::into_iter()
             // compare str
@@ -80,18 +113,7 @@ where
             })
     }
 }
-// TODO, fix main.rs help brittleness
-// do more stupid stuff with macros, pull the docstring as const help text
-//
-// #[derive(StrEnum, Copy, Clone, Debug)]
-// pub enum RngType {
-//     /// the system's native secure RNG
-//     #[default]
-//     OsRng,
-//     ...
-// }
-
-/// The different ways words can be transformed.
+/// The different ways words can be transformed, which can be chained to apply one after another.
 #[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
 pub enum WordTransformationType {
     None,
@@ -112,6 +134,36 @@ pub enum WordTransformationType {
     AlternatingUpperLower,
     /// correct HORSE battery staple
     RandomUpperLower,
+    /// cOrReCt HoRSe bAtTEry sTApLe
+    RandomCase,
+    /// c0rr3c7 h0r$3 b4773ry $74pl3
+    Leet,
+    /// c0rrec7 hor$e b4tt3ry s7apl3
+    RandomLeet,
+    /// tcerroc esroh yrettab elpats
+    Reverse,
+}
+
+/// The different ways the uppercase-limit of random-case can be applied.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum UppercaseLimitType {
+    /// uppercase uppercase-limit randomly chosen letters in each word
+    #[default]
+    Word,
+    /// uppercase uppercase-limit randomly chosen letters in the whole password
+    Password,
+}
+
+/// The different ways groups of digits and symbols can be placed between words.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum GroupPlacement {
+    /// digits only appear in the digits-before and digits-after pseudo-words
+    #[default]
+    None,
+    /// place a group in every gap between words, as in *correct7horse4battery*
+    EveryGap,
+    /// place group-count groups in randomly chosen gaps between words
+    RandomGaps,
 }
 
 /// The different ways padding can be applied.
@@ -121,18 +173,106 @@ pub enum PaddingType {
     /// add padding-length padding-characters to front and back
     #[default]
     Fixed,
-    /// if unpadded password is less than padding-length, append padding-characters to desired length
+    /// if unpadded password is less than padding-length, append padding-characters to meet length
     Adaptive,
+    /// add padding-length distinct padding-characters to front, mirrored on back as in *#[PASSWORD]#*
+    Symmetric,
+}
+
+/// The different ways padding-characters can be chosen.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum PaddingMode {
+    /// repeat one padding-character, as in *!!PASSWORD!!*
+    #[default]
+    Single,
+    /// choose each padding-character separately, as in *!@PASSWORD$!*
+    PerCharacter,
+}
+
+/// The different ways words and pseudo-words can be separated.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum SeparatorType {
+    /// place the same separator-character between every word
+    #[default]
+    Single,
+    /// choose a separator-character for each gap between words
+    PerGap,
+    /// choose a separator-token for each gap between words, as in *correct::HORSE and battery-STAPLE*
+    Tokens,
 }
 
 /// The different random number generator options.
 #[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
 pub enum RngType {
-    /// the system's native secure RNG
+    /// the operating system's secure RNG, asked for every choice
     #[default]
     OsRng,
-    /// a reasonably secure userspace RNG
+    /// rand's ThreadRng, ChaCha12 seeded from the operating system and reseeded every 64 KiB
     Csprng,
+    /// ChaCha with 8 rounds, seeded once from the operating system
+    #[cfg(feature = "chacha")]
+    Chacha8,
+    /// ChaCha with 12 rounds, seeded once from the operating system
+    #[cfg(feature = "chacha")]
+    Chacha12,
+    /// ChaCha with 20 rounds, seeded once from the operating system
+    #[cfg(feature = "chacha")]
+    Chacha20,
+    /// physical dice rolls, read from stdin
+    #[cfg(feature = "dice")]
+    Dice,
+}
+
+/// The different ways of printing passwords.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// one password per line
+    #[default]
+    Plain,
+    /// a JSON array of objects describing each password
+    Json,
+    /// one JSON object per line
+    Jsonl,
+    /// comma separated values with a header row
+    Csv,
+    /// tab separated values with a header row
+    Tsv,
+}
+
+/// The kinds of characters a password policy can require.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum CharacterClass {
+    /// an uppercase letter
+    Upper,
+    /// a lowercase letter
+    #[default]
+    Lower,
+    /// a digit from 0 to 9
+    Digit,
+    /// anything that is not a letter or digit, including spaces
+    Symbol,
+}
+
+/// Built-in configurations, modelled on the presets that ship with Crypt::HSXKPasswd.
+#[derive(StrEnum, Copy, Clone, Debug, PartialEq)]
+pub enum Preset {
+    /// the fmn-passgen defaults
+    #[default]
+    Default,
+    /// for websites that allow passwords of up to 32 characters
+    Web32,
+    /// for websites that only allow passwords of up to 16 characters
+    Web16,
+    /// a 63 character WPA2 key
+    Wifi,
+    /// for Apple IDs, which require upper and lower case letters and digits
+    AppleId,
+    /// a 14 character password for legacy Windows NTLM
+    Ntlm,
+    /// a sentence-like answer to a security question
+    SecurityQ,
+    /// correct-horse-battery-staple, as in the comic
+    Xkcd,
 }
 
 #[cfg(test)]
@@ -140,6 +280,13 @@ mod test {
     use super::*;
     use std::mem::discriminant;
 
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn test_strenum_feature_gated_members() {
+        assert_eq!(RngType::Chacha20.to_string(), "chacha20");
+        assert_eq!(*RngType::to_member("chacha8").unwrap(), RngType::Chacha8);
+    }
+
     #[test]
     fn test_strenum_name() {
         assert_eq!(RngType::NAME, "RngType");
@@ -164,6 +311,15 @@ mod test {
         assert_eq!("os-rng", RngType::OsRng.to_static_str());
     }
 
+    #[test]
+    fn test_strenum_description() {
+        assert_eq!(
+            "the operating system's secure RNG, asked for every choice",
+            RngType::OsRng.description()
+        );
+        assert_eq!("", WordTransformationType::None.description());
+    }
+
     #[test]
     fn test_strenum_to_member_success() {
         assert_eq!(
@@ -172,6 +328,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_strenum_leet_round_trip() {
+        for member in [
+            WordTransformationType::Leet,
+            WordTransformationType::RandomLeet,
+        ] {
+            assert_eq!(
+                *WordTransformationType::to_member(member.to_static_str()).unwrap(),
+                member
+            );
+        }
+        assert_eq!(
+            WordTransformationType::RandomLeet.to_string(),
+            "random-leet"
+        );
+    }
+
+    #[test]
+    fn test_strenum_preset_names() {
+        let names = Preset::into_iter().map(|(s, _)| *s).collect::<Vec<&str>>();
+        assert_eq!(
+            names,
+            [
+                "default",
+                "web32",
+                "web16",
+                "wifi",
+                "apple-id",
+                "ntlm",
+                "security-q",
+                "xkcd"
+            ]
+        );
+    }
+
     #[test]
     fn test_strenum_to_member_err() {
         RngType::to_member("not-a-member").unwrap_err();
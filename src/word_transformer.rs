@@ -1,20 +1,158 @@
 //! The different ways that words can be transformed.
-use rand::prelude::*;
+//!
+//! Case is changed with Unicode's default case mapping, so a letter may become more than one,
+//! as `ß` becomes `SS`, and a Greek sigma at the end of a word becomes `ς` when the whole word is lowercased.
+//! Language specific rules, such as Turkish dotted and dotless `i`, are not applied.
+//!
+//! Each transformation is a function of the words.
+//! [`WordTransformation`] applies one of them and [`Config`] applies its chain of them
+//! through the [`WordTransformer`] trait, which library users can implement for their own.
+use std::fmt;
+
+use crate::config::Config;
+use crate::consts::default;
+use crate::rng::RandomChoice;
+use crate::types::UppercaseLimitType;
+use crate::types::WordTransformationType;
+
+/// A way of transforming the words chosen for a password.
+///
+/// A custom transformer can be given to [`crate::password_maker::PasswordMaker::with_transformer`],
+/// which applies it after the configured [`WordTransformationType`]s.
+/// With a template, it is given each word on its own, after the word's token has set its case.
+/// Its random choices are not counted in the entropy estimate, which is therefore too high
+/// if it turns different words into the same word, as by truncating them.
+///
+/// # Example
+/// ```
+/// use fmn_passgen::password_maker::PasswordMaker;
+/// use fmn_passgen::rng::RandomChoice;
+/// use fmn_passgen::word_transformer::WordTransformer;
+/// use rand::rngs::OsRng;
+///
+/// /// Add a random digit to the end of every word.
+/// #[derive(Debug)]
+/// struct DigitSuffix;
+///
+/// impl WordTransformer for DigitSuffix {
+///     fn transform(&self, rng: &mut dyn RandomChoice, words: Vec<String>) -> Vec<String> {
+///         words
+///             .into_iter()
+///             .map(|word| format!("{word}{}", rng.choose_index(10)))
+///             .collect()
+///     }
+/// }
+///
+/// let mut maker = PasswordMaker::<OsRng>::default().with_transformer(DigitSuffix);
+/// let generated = maker.generate_password().unwrap();
+/// assert!(generated.words.iter().all(|word| word.ends_with(|c: char| c.is_ascii_digit())));
+/// ```
+pub trait WordTransformer: fmt::Debug + Send + Sync {
+    /// Transform `words`, making any random choices with `rng`.
+    fn transform(&self, rng: &mut dyn RandomChoice, words: Vec<String>) -> Vec<String>;
+}
+
+/// A single built-in transformation along with the settings it needs,
+/// so that it can be used on its own, such as after a custom [`WordTransformer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordTransformation {
+    /// the transformation to apply
+    pub step: WordTransformationType,
+    /// see [`Config::leet_table`]
+    pub leet_table: Vec<(char, char)>,
+    /// see [`Config::uppercase_limit`]
+    pub uppercase_limit: u8,
+    /// see [`Config::uppercase_limit_type`]
+    pub uppercase_limit_type: UppercaseLimitType,
+}
+
+impl WordTransformation {
+    /// Apply `step` with the default settings.
+    pub fn new(step: WordTransformationType) -> Self {
+        Self {
+            step,
+            leet_table: default::LEET_TABLE.to_vec(),
+            uppercase_limit: default::UPPERCASE_LIMIT,
+            uppercase_limit_type: UppercaseLimitType::default(),
+        }
+    }
+    /// Apply `step` with the settings of `config`.
+    pub fn from_config(step: WordTransformationType, config: &Config) -> Self {
+        Self {
+            step,
+            leet_table: config.leet_table.clone(),
+            uppercase_limit: config.uppercase_limit,
+            uppercase_limit_type: config.uppercase_limit_type,
+        }
+    }
+}
+
+impl WordTransformer for WordTransformation {
+    fn transform(&self, rng: &mut dyn RandomChoice, words: Vec<String>) -> Vec<String> {
+        apply(
+            self.step,
+            rng,
+            words,
+            &self.leet_table,
+            self.uppercase_limit,
+            self.uppercase_limit_type,
+        )
+    }
+}
+
+/// Applies [`Config::word_transformation`] in order, using the other settings of the [`Config`]
+/// for the transformations that need them, as a [`WordTransformation`] would.
+impl WordTransformer for Config {
+    fn transform(&self, rng: &mut dyn RandomChoice, words: Vec<String>) -> Vec<String> {
+        self.word_transformation.iter().fold(words, |words, step| {
+            apply(
+                *step,
+                rng,
+                words,
+                &self.leet_table,
+                self.uppercase_limit,
+                self.uppercase_limit_type,
+            )
+        })
+    }
+}
+
+/// Apply a single transformation, using `leet_table` and the uppercase limit where needed.
+fn apply(
+    step: WordTransformationType,
+    rng: &mut dyn RandomChoice,
+    words: Vec<String>,
+    leet_table: &[(char, char)],
+    uppercase_limit: u8,
+    uppercase_limit_type: UppercaseLimitType,
+) -> Vec<String> {
+    match step {
+        WordTransformationType::None => words,
+        WordTransformationType::Lower => lower(words),
+        WordTransformationType::Upper => upper(words),
+        WordTransformationType::CapitalizeFirst => capitalize_first(words),
+        WordTransformationType::CapitalizeLast => capitalize_last(words),
+        WordTransformationType::CapitalizeNotFirst => capitalize_not_first(words),
+        WordTransformationType::AlternatingLowerUpper => alternating_lower_upper(words),
+        WordTransformationType::AlternatingUpperLower => alternating_upper_lower(words),
+        WordTransformationType::RandomUpperLower => random_upper_lower(rng, words),
+        WordTransformationType::RandomCase => {
+            random_case(rng, words, uppercase_limit as usize, uppercase_limit_type)
+        }
+        WordTransformationType::Leet => leet(words, leet_table),
+        WordTransformationType::RandomLeet => random_leet(rng, words, leet_table),
+        WordTransformationType::Reverse => reverse(words),
+    }
+}
 
 /// correct horse battery staple
 pub fn lower(words: Vec<String>) -> Vec<String> {
-    words
-        .into_iter()
-        .map(|word| word.to_ascii_lowercase())
-        .collect()
+    words.into_iter().map(|word| word.to_lowercase()).collect()
 }
 
 /// CORRECT HORSE BATTERY STAPLE
 pub fn upper(words: Vec<String>) -> Vec<String> {
-    words
-        .into_iter()
-        .map(|word| word.to_ascii_uppercase())
-        .collect()
+    words.into_iter().map(|word| word.to_uppercase()).collect()
 }
 
 /// Correct Horse Battery Staple
@@ -48,9 +186,9 @@ pub fn alternating_lower_upper(words: Vec<String>) -> Vec<String> {
         .enumerate()
         .map(|(i, word)| {
             if i % 2 == 0 {
-                word.to_ascii_lowercase()
+                word.to_lowercase()
             } else {
-                word.to_ascii_uppercase()
+                word.to_uppercase()
             }
         })
         .collect()
@@ -63,63 +201,213 @@ pub fn alternating_upper_lower(words: Vec<String>) -> Vec<String> {
         .enumerate()
         .map(|(i, word)| {
             if i % 2 == 0 {
-                word.to_ascii_uppercase()
+                word.to_uppercase()
             } else {
-                word.to_ascii_lowercase()
+                word.to_lowercase()
             }
         })
         .collect()
 }
 
 /// correct HORSE battery staple
-pub fn random_upper_lower(rng: &mut (impl Rng + ?Sized), words: Vec<String>) -> Vec<String> {
+pub fn random_upper_lower(
+    rng: &mut (impl RandomChoice + ?Sized),
+    words: Vec<String>,
+) -> Vec<String> {
     words
         .into_iter()
         .map(|word| {
-            if rng.random::<bool>() {
-                word.to_ascii_uppercase()
+            if rng.flip_coin() {
+                word.to_uppercase()
             } else {
-                word.to_ascii_lowercase()
+                word.to_lowercase()
             }
         })
         .collect()
 }
 
+/// cOrReCt HoRSe bAtTEry sTApLe
+///
+/// Flip a coin for the case of every letter, or if `limit` is not 0, uppercase `limit` randomly
+/// chosen letters of each word or of all the words, as `limit_type` says, and lowercase the rest.
+/// Letters are any characters with an uppercase and a lowercase form, not just ASCII ones.
+pub fn random_case(
+    rng: &mut (impl RandomChoice + ?Sized),
+    words: Vec<String>,
+    limit: usize,
+    limit_type: UppercaseLimitType,
+) -> Vec<String> {
+    let words: Vec<Vec<char>> = words.iter().map(|word| word.chars().collect()).collect();
+    let letters: Vec<Vec<usize>> = words
+        .iter()
+        .map(|word| (0..word.len()).filter(|i| is_cased(word[*i])).collect())
+        .collect();
+    let mut uppercase: Vec<Vec<bool>> = words.iter().map(|word| vec![false; word.len()]).collect();
+    match limit_type {
+        _ if limit == 0 => {
+            for (w, positions) in letters.iter().enumerate() {
+                for i in positions {
+                    uppercase[w][*i] = rng.flip_coin();
+                }
+            }
+        }
+        UppercaseLimitType::Word => {
+            for (w, positions) in letters.into_iter().enumerate() {
+                for i in choose_distinct(rng, positions, limit) {
+                    uppercase[w][i] = true;
+                }
+            }
+        }
+        UppercaseLimitType::Password => {
+            let positions = letters
+                .into_iter()
+                .enumerate()
+                .flat_map(|(w, positions)| positions.into_iter().map(move |i| (w, i)))
+                .collect();
+            for (w, i) in choose_distinct(rng, positions, limit) {
+                uppercase[w][i] = true;
+            }
+        }
+    }
+    words
+        .into_iter()
+        .zip(uppercase)
+        .map(|(word, uppercase)| {
+            let mut result = String::with_capacity(word.len());
+            for (c, upper) in word.into_iter().zip(uppercase) {
+                match (is_cased(c), upper) {
+                    (false, _) => result.push(c),
+                    (true, true) => result.extend(c.to_uppercase()),
+                    (true, false) => result.extend(c.to_lowercase()),
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+/// How many letters of `word` [`random_case`] decides the case of,
+/// leaving out any that `table` could substitute.
+pub fn random_case_letters(word: &str, table: &[(char, char)]) -> usize {
+    word.chars()
+        .filter(|c| is_cased(*c) && leet_substitute(*c, table).is_none())
+        .count()
+}
+
+/// Whether `c` has different uppercase and lowercase forms.
+fn is_cased(c: char) -> bool {
+    !c.to_uppercase().eq(c.to_lowercase())
+}
+
+/// Choose `k` of `items` without replacement, or all of them if there are fewer.
+fn choose_distinct<T>(
+    rng: &mut (impl RandomChoice + ?Sized),
+    mut items: Vec<T>,
+    k: usize,
+) -> Vec<T> {
+    let k = k.min(items.len());
+    for i in 0..k {
+        let j = i + rng.choose_index(items.len() - i);
+        items.swap(i, j);
+    }
+    items.truncate(k);
+    items
+}
+
+/// c0rr3c7 h0r$3 b4773ry $74pl3
+///
+/// Substitute every character found in `table`, ignoring case.
+pub fn leet(words: Vec<String>, table: &[(char, char)]) -> Vec<String> {
+    words
+        .into_iter()
+        .map(|word| {
+            word.chars()
+                .map(|c| leet_substitute(c, table).unwrap_or(c))
+                .collect()
+        })
+        .collect()
+}
+
+/// c0rrec7 hor$e b4tt3ry s7apl3
+///
+/// Substitute each character found in `table` with a probability of one half.
+pub fn random_leet(
+    rng: &mut (impl RandomChoice + ?Sized),
+    words: Vec<String>,
+    table: &[(char, char)],
+) -> Vec<String> {
+    words
+        .into_iter()
+        .map(|word| {
+            word.chars()
+                .map(|c| match leet_substitute(c, table) {
+                    Some(substitute) if rng.flip_coin() => substitute,
+                    _ => c,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// tcerroc esroh yrettab elpats
+pub fn reverse(words: Vec<String>) -> Vec<String> {
+    words
+        .into_iter()
+        .map(|word| word.chars().rev().collect())
+        .collect()
+}
+
+/// How many characters of `word` can be substituted by `table`,
+/// each of which is worth one bit with [`random_leet`].
+pub fn leet_substitutable(word: &str, table: &[(char, char)]) -> usize {
+    word.chars()
+        .filter(|c| leet_substitute(*c, table).is_some())
+        .count()
+}
+
+/// The substitute for `c` in `table`, if it has one.
+fn leet_substitute(c: char, table: &[(char, char)]) -> Option<char> {
+    table
+        .iter()
+        .find(|(from, _)| from.to_lowercase().eq(c.to_lowercase()))
+        .map(|(_, to)| *to)
+}
+
 /// foo -> Foo
 fn capitalize_first_char(word: &str) -> String {
-    let first = word.chars().take(1).map(|c| c.to_ascii_uppercase());
-    first.chain(word.chars().skip(1)).collect()
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
 }
 
 /// foo -> foO
 fn capitalize_last_char(word: &str) -> String {
-    // UTF character length weirdness reminder
-    let num_chars = word.chars().count();
-    let len_minus_1 = num_chars.saturating_sub(1);
-    word.chars()
-        .take(len_minus_1)
-        .chain(
-            word.chars()
-                .skip(len_minus_1)
-                .take(1)
-                .map(|c| c.to_ascii_uppercase()),
-        )
-        .collect()
+    let mut chars = word.chars();
+    chars.next_back().map_or_else(String::new, |last| {
+        let mut result = chars.as_str().to_owned();
+        result.extend(last.to_uppercase());
+        result
+    })
 }
 
 /// foo -> fOO
 fn capitalize_not_first_char(word: &str) -> String {
-    word.chars()
-        .take(1)
-        .chain(word.chars().skip(1).map(|c| c.to_ascii_uppercase()))
-        .collect()
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        let mut result = first.to_string();
+        result.push_str(&chars.as_str().to_uppercase());
+        result
+    })
 }
 
 // TODO these tests would make great doctests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ConfigBuilder;
     use crate::test_helpers::*;
+    use rand::SeedableRng;
     use rand::rngs::SmallRng;
 
     #[test]
@@ -157,6 +445,48 @@ mod tests {
         assert_eq!("a".to_owned(), capitalize_not_first_char("a"));
     }
 
+    #[test]
+    fn test_capitalize_multi_byte() {
+        assert_eq!("Ärger", capitalize_first_char("ärger"));
+        assert_eq!("SSa", capitalize_first_char("ßa"));
+        assert_eq!("cafÉ", capitalize_last_char("café"));
+        assert_eq!("straSS", capitalize_last_char("straß"));
+        assert_eq!("ä", capitalize_last_char("Ä").to_lowercase());
+        assert_eq!("äRGER", capitalize_not_first_char("ärger"));
+        assert_eq!("sTRASSE", capitalize_not_first_char("straße"));
+        assert_eq!("é", capitalize_not_first_char("é"));
+    }
+
+    #[test]
+    fn test_word_transformer_unicode_case() {
+        let words = || {
+            vec![
+                "straße".to_owned(),
+                "ΟΔΟΣ".to_owned(),
+                "ışık".to_owned(),
+                "Ärger".to_owned(),
+            ]
+        };
+        assert_eq!(upper(words()), ["STRASSE", "ΟΔΟΣ", "IŞIK", "ÄRGER"]);
+        // a sigma at the end of a word has its own lowercase form
+        assert_eq!(lower(words()), ["straße", "οδος", "ışık", "ärger"]);
+        assert_eq!(
+            alternating_lower_upper(words()),
+            ["straße", "ΟΔΟΣ", "ışık", "ÄRGER"]
+        );
+        assert_eq!(
+            capitalize_first(words()),
+            ["Straße", "ΟΔΟΣ", "Işık", "Ärger"]
+        );
+    }
+
+    #[test]
+    fn test_word_transformer_leet_ignores_unicode_case() {
+        let table = [('ä', '@'), ('ö', '0')];
+        assert_eq!(leet(vec!["ÄRGER-öl".to_owned()], &table), ["@RGER-0l"]);
+        assert_eq!(leet_substitutable("ÖL", &table), 1);
+    }
+
     #[test]
     fn test_word_transformer_lower() {
         let result = lower(make_wordlist());
@@ -233,4 +563,128 @@ mod tests {
         assert_eq!("hello", &result_2[0]);
         assert_eq!("WORLD", &result_2[1]);
     }
+
+    #[test]
+    fn test_word_transformer_random_case() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let words: Vec<String> = (0..20).map(|_| "ärger-öl".to_owned()).collect();
+        let result = random_case(&mut rng, words, 0, UppercaseLimitType::Word);
+        for word in &result {
+            assert_eq!(word.to_lowercase(), "ärger-öl");
+        }
+        assert!(result.iter().any(|word| word.contains('Ä')));
+        assert!(result.iter().any(|word| word.contains('ä')));
+        assert!(result.iter().any(|word| word != &result[0]));
+        // some letters have more than one character when uppercase
+        let result = random_case(&mut rng, vec!["ß".to_owned()], 1, UppercaseLimitType::Word);
+        assert_eq!(result, ["SS"]);
+    }
+
+    #[test]
+    fn test_word_transformer_random_case_limit() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let words = vec!["correct".to_owned(), "HORSE".to_owned(), "ok".to_owned()];
+        let uppercase = |word: &String| word.chars().filter(|c| c.is_uppercase()).count();
+        for _ in 0..20 {
+            let result = random_case(&mut rng, words.clone(), 3, UppercaseLimitType::Word);
+            assert_eq!(
+                result.iter().map(uppercase).collect::<Vec<usize>>(),
+                [3, 3, 2]
+            );
+            let result = random_case(&mut rng, words.clone(), 3, UppercaseLimitType::Password);
+            assert_eq!(result.iter().map(uppercase).sum::<usize>(), 3);
+            assert_eq!(
+                result
+                    .iter()
+                    .map(|word| word.to_lowercase())
+                    .collect::<Vec<String>>(),
+                ["correct", "horse", "ok"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_word_transformer_random_case_letters() {
+        assert_eq!(random_case_letters("straße-9", &[]), 6);
+        assert_eq!(random_case_letters("horse", &[('o', '0'), ('S', '$')]), 3);
+    }
+
+    #[test]
+    fn test_config_transform() {
+        let mut config = ConfigBuilder::new()
+            .word_transformation(Some("upper,leet,reverse".to_owned()))
+            .leet_table(Some("o0".to_owned()))
+            .build()
+            .unwrap();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let words = vec!["horse".to_owned(), "staple".to_owned()];
+        assert_eq!(
+            config.transform(&mut rng, words.clone()),
+            ["ESR0H", "ELPATS"]
+        );
+        config.word_transformation = vec![WordTransformationType::RandomUpperLower];
+        let mut expected_rng = SmallRng::seed_from_u64(2);
+        let mut rng = SmallRng::seed_from_u64(2);
+        assert_eq!(
+            config.transform(&mut rng, words.clone()),
+            random_upper_lower(&mut expected_rng, words.clone())
+        );
+        config.word_transformation.clear();
+        assert_eq!(config.transform(&mut rng, words.clone()), words);
+    }
+
+    #[test]
+    fn test_word_transformation() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let words = vec!["horse".to_owned(), "staple".to_owned()];
+        let leet = WordTransformation::new(WordTransformationType::Leet);
+        assert_eq!(leet.transform(&mut rng, words.clone()), ["h0r$3", "$74pl3"]);
+        // the settings come from the config
+        let config = ConfigBuilder::new()
+            .leet_table(Some("o0".to_owned()))
+            .uppercase_limit(Some("1".to_owned()))
+            .build()
+            .unwrap();
+        let leet = WordTransformation::from_config(WordTransformationType::Leet, &config);
+        assert_eq!(leet.transform(&mut rng, words.clone()), ["h0rse", "staple"]);
+        let random_case =
+            WordTransformation::from_config(WordTransformationType::RandomCase, &config);
+        for word in random_case.transform(&mut rng, words) {
+            assert_eq!(word.chars().filter(|c| c.is_uppercase()).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_word_transformer_reverse() {
+        let result = reverse(vec!["horse".to_owned(), "Staple".to_owned(), String::new()]);
+        assert_eq!(result, ["esroh", "elpatS", ""]);
+    }
+
+    #[test]
+    fn test_word_transformer_leet() {
+        let table = [('a', '4'), ('e', '3'), ('o', '0'), ('s', '$')];
+        let result = leet(vec!["horse".to_owned(), "STAPLE".to_owned()], &table);
+        assert_eq!(result, ["h0r$3", "$T4PL3"]);
+        assert_eq!(leet_substitutable("horse", &table), 3);
+        assert_eq!(leet_substitutable("STAPLE", &table), 3);
+        assert_eq!(leet(vec!["horse".to_owned()], &[]), ["horse"]);
+    }
+
+    #[test]
+    fn test_word_transformer_random_leet() {
+        let table = [('a', '4'), ('e', '3'), ('o', '0'), ('s', '$')];
+        let mut rng = SmallRng::seed_from_u64(1);
+        let words: Vec<String> = (0..20).map(|_| "seesaw".to_owned()).collect();
+        let result = random_leet(&mut rng, words, &table);
+        for word in &result {
+            for (c, original) in word.chars().zip("seesaw".chars()) {
+                assert!(
+                    c == original || leet(vec![original.to_string()], &table)[0] == c.to_string()
+                );
+            }
+        }
+        assert!(result.iter().any(|word| word.contains('s')));
+        assert!(result.iter().any(|word| word.contains('$')));
+        assert!(result.iter().any(|word| word != &result[0]));
+    }
 }
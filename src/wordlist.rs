@@ -0,0 +1,412 @@
+//! Loading and validation of custom wordlists.
+//!
+//! Two formats are understood:
+//!
+//! - plain, one word per line
+//! - numbered, as distributed by the EFF and classic diceware, one `11111\tabacus` pair per line
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Represent the ways in which a wordlist can fail to load.
+///
+/// Line numbers are 1-indexed to match what a text editor would show.
+#[derive(Debug)]
+pub enum WordlistError {
+    /// the file could not be read
+    Io(PathBuf, io::Error),
+    /// a line is not valid UTF-8
+    InvalidUtf8(usize),
+    /// a line is empty or contains only whitespace
+    EmptyLine(usize),
+    /// a line contains whitespace between non-whitespace characters
+    InnerWhitespace(usize, String),
+    /// the wordlist contains no words
+    Empty,
+    /// a numbered line has a malformed dice index
+    InvalidDiceIndex(usize, String),
+    /// a dice index appears more than once in a numbered wordlist
+    DuplicateDiceIndex(usize, String),
+    /// a word appears more than once in a numbered wordlist
+    DuplicateWord(usize, String),
+    /// a numbered wordlist does not have exactly one word for every possible roll
+    IncompleteDiceCoverage(usize, usize),
+}
+
+impl fmt::Display for WordlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "unable to read wordlist `{}`: {e}", path.display()),
+            Self::InvalidUtf8(line) => write!(f, "wordlist line {line} is not valid UTF-8"),
+            Self::EmptyLine(line) => write!(f, "wordlist line {line} is empty"),
+            Self::InnerWhitespace(line, word) => {
+                write!(
+                    f,
+                    "wordlist line {line} (`{word}`) must not contain whitespace"
+                )
+            }
+            Self::Empty => write!(f, "wordlist must contain at least one word"),
+            Self::InvalidDiceIndex(line, index) => write!(
+                f,
+                "wordlist line {line} has an invalid dice index `{index}`, expected only the digits 1-6"
+            ),
+            Self::DuplicateDiceIndex(line, index) => {
+                write!(f, "wordlist line {line} repeats the dice index `{index}`")
+            }
+            Self::DuplicateWord(line, word) => {
+                write!(f, "wordlist line {line} repeats the word `{word}`")
+            }
+            Self::IncompleteDiceCoverage(expected, found) => write!(
+                f,
+                "numbered wordlist must have {expected} words to cover every roll, found {found}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WordlistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The highest number of dice per word we will accept, 6^12 is already over 2 billion words.
+const MAX_DICE_PER_WORD: u32 = 12;
+
+/// Dice faces, in order.
+const DICE_FACES: [char; 6] = ['1', '2', '3', '4', '5', '6'];
+
+/// A validated list of words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wordlist {
+    /// the words, in dice order if the wordlist is numbered
+    pub words: Vec<String>,
+    /// how many dice select a word, if the wordlist is numbered
+    pub dice_per_word: Option<u32>,
+}
+
+impl Wordlist {
+    /// Get the dice roll that selects the word at `index`, eg `"11111"` for the first word.
+    ///
+    /// Returns [`None`] for plain wordlists or an out of range `index`.
+    pub fn dice_index(&self, index: usize) -> Option<String> {
+        let dice = self.dice_per_word?;
+        if index >= self.words.len() {
+            return None;
+        }
+        let mut remainder = index;
+        let mut rolls = vec!['1'; dice as usize];
+        for roll in rolls.iter_mut().rev() {
+            *roll = DICE_FACES[remainder % 6];
+            remainder /= 6;
+        }
+        Some(rolls.into_iter().collect())
+    }
+    /// Get the word selected by a dice roll such as `"11111"`.
+    ///
+    /// Returns [`None`] for plain wordlists or a roll that is not valid for this wordlist.
+    pub fn word_for_roll(&self, roll: &str) -> Option<&str> {
+        let dice = self.dice_per_word?;
+        if roll.chars().count() != dice as usize {
+            return None;
+        }
+        let index = parse_dice_index(roll)?;
+        self.words.get(index).map(String::as_str)
+    }
+}
+
+/// Convert a string of dice faces into a 0-based index.
+fn parse_dice_index(roll: &str) -> Option<usize> {
+    roll.chars().try_fold(0usize, |acc, c| {
+        let face = DICE_FACES.iter().position(|f| *f == c)?;
+        acc.checked_mul(6)?.checked_add(face)
+    })
+}
+
+/// Read a newline delimited wordlist from a file.
+///
+/// See [`parse_wordlist`] for the validation rules.
+pub fn read_wordlist(path: impl AsRef<Path>) -> Result<Wordlist, WordlistError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| WordlistError::Io(path.to_path_buf(), e))?;
+    parse_wordlist(&bytes)
+}
+
+/// Parse a newline delimited wordlist.
+///
+/// Each line is trimmed of surrounding whitespace and must then be a single, non-empty word,
+/// optionally preceded by a dice index and whitespace.
+/// Lines are normalized to Unicode NFC, so that words which look the same are the same,
+/// whichever way their accents were written.
+/// The format is decided by the first line; if it is numbered, every line must be.
+///
+/// For plain wordlists, duplicate words are discarded, keeping the first occurrence.
+///
+/// For numbered wordlists, every index must be made up of the same number of dice,
+/// and every possible roll must select exactly one word.
+/// The words are returned in dice order.
+///
+/// A single trailing newline at the end of the input is permitted.
+pub fn parse_wordlist(bytes: &[u8]) -> Result<Wordlist, WordlistError> {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    if bytes.is_empty() {
+        return Err(WordlistError::Empty);
+    }
+    let mut lines = Vec::new();
+    for (i, line) in bytes.split(|b| *b == b'\n').enumerate() {
+        let line_number = i + 1;
+        let Ok(line) = std::str::from_utf8(line) else {
+            return Err(WordlistError::InvalidUtf8(line_number));
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            return Err(WordlistError::EmptyLine(line_number));
+        }
+        lines.push((line_number, line.nfc().collect::<String>()));
+    }
+    let lines: Vec<(usize, &str)> = lines
+        .iter()
+        .map(|(line_number, line)| (*line_number, line.as_str()))
+        .collect();
+    if lines.first().is_some_and(|(_, line)| is_numbered(line)) {
+        parse_numbered(&lines)
+    } else {
+        parse_plain(&lines)
+    }
+}
+
+/// Check if a line looks like `11111\tabacus`.
+fn is_numbered(line: &str) -> bool {
+    line.split_once(char::is_whitespace)
+        .is_some_and(|(index, _)| index.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Ensure a word does not contain whitespace.
+fn validate_word(line_number: usize, word: &str) -> Result<(), WordlistError> {
+    if word.contains(char::is_whitespace) {
+        Err(WordlistError::InnerWhitespace(line_number, word.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse trimmed, non-empty lines containing only words.
+fn parse_plain(lines: &[(usize, &str)]) -> Result<Wordlist, WordlistError> {
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    for &(line_number, word) in lines {
+        validate_word(line_number, word)?;
+        if seen.insert(word) {
+            words.push(word.to_owned());
+        }
+    }
+    Ok(Wordlist {
+        words,
+        dice_per_word: None,
+    })
+}
+
+/// Parse trimmed, non-empty lines containing dice indices and words.
+fn parse_numbered(lines: &[(usize, &str)]) -> Result<Wordlist, WordlistError> {
+    let mut dice_per_word = None;
+    let mut seen_words = HashSet::new();
+    let mut entries = Vec::with_capacity(lines.len());
+    for &(line_number, line) in lines {
+        let Some((index, word)) = line.split_once(char::is_whitespace) else {
+            return Err(WordlistError::InvalidDiceIndex(
+                line_number,
+                line.to_owned(),
+            ));
+        };
+        let word = word.trim();
+        validate_word(line_number, word)?;
+        let invalid_index = || WordlistError::InvalidDiceIndex(line_number, index.to_owned());
+        let dice = u32::try_from(index.len()).map_err(|_| invalid_index())?;
+        if dice > MAX_DICE_PER_WORD || dice != *dice_per_word.get_or_insert(dice) {
+            return Err(invalid_index());
+        }
+        let position = parse_dice_index(index).ok_or_else(invalid_index)?;
+        if !seen_words.insert(word) {
+            return Err(WordlistError::DuplicateWord(line_number, word.to_owned()));
+        }
+        entries.push((position, line_number, index, word));
+    }
+    entries.sort_unstable_by_key(|(position, ..)| *position);
+    if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        // report the later of the two lines
+        let (_, line_number, index, _) = pair[0].max(pair[1]);
+        return Err(WordlistError::DuplicateDiceIndex(
+            line_number,
+            index.to_owned(),
+        ));
+    }
+    let expected = 6usize.pow(dice_per_word.unwrap_or(0));
+    if entries.len() != expected {
+        return Err(WordlistError::IncompleteDiceCoverage(
+            expected,
+            entries.len(),
+        ));
+    }
+    Ok(Wordlist {
+        words: entries
+            .into_iter()
+            .map(|(.., word)| word.to_owned())
+            .collect(),
+        dice_per_word,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wordlist_ok() {
+        let result = parse_wordlist(b"correct\r\n  horse\t\nbattery\nstaple\n").unwrap();
+        assert!(result.dice_per_word.is_none());
+        let result = result.words;
+        assert_eq!(result, ["correct", "horse", "battery", "staple"]);
+    }
+
+    #[test]
+    fn test_parse_wordlist_no_trailing_newline() {
+        let result = parse_wordlist(b"correct\nhorse").unwrap().words;
+        assert_eq!(result, ["correct", "horse"]);
+    }
+
+    #[test]
+    fn test_parse_wordlist_deduplicates() {
+        let result = parse_wordlist(b"horse\ncorrect\nhorse\n").unwrap().words;
+        assert_eq!(result, ["horse", "correct"]);
+    }
+
+    #[test]
+    fn test_parse_wordlist_normalizes() {
+        // the first `é` is decomposed into `e` and a combining acute accent
+        let result = parse_wordlist("cafe\u{301}\ncafé\nstraße\n".as_bytes())
+            .unwrap()
+            .words;
+        assert_eq!(result, ["café", "straße"]);
+        assert_eq!(result[0].chars().count(), 4);
+        let err = parse_wordlist("1\tcafe\u{301}\n2\tcafé\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, WordlistError::DuplicateWord(2, word) if word == "café"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_empty() {
+        assert!(matches!(parse_wordlist(b""), Err(WordlistError::Empty)));
+        assert!(matches!(parse_wordlist(b"\n"), Err(WordlistError::Empty)));
+    }
+
+    #[test]
+    fn test_parse_wordlist_empty_line() {
+        let err = parse_wordlist(b"correct\n \nhorse\n").unwrap_err();
+        assert!(matches!(err, WordlistError::EmptyLine(2)));
+    }
+
+    #[test]
+    fn test_parse_wordlist_inner_whitespace() {
+        let err = parse_wordlist(b"correct\nhorse battery\n").unwrap_err();
+        assert!(matches!(err, WordlistError::InnerWhitespace(2, word) if word == "horse battery"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_invalid_utf8() {
+        let err = parse_wordlist(b"correct\nhorse\n\xff\xfe\n").unwrap_err();
+        assert!(matches!(err, WordlistError::InvalidUtf8(3)));
+    }
+
+    /// Build a numbered wordlist with `dice` dice per word, in reverse dice order.
+    fn make_numbered(dice: u32) -> String {
+        let count = 6usize.pow(dice);
+        let wordlist = Wordlist {
+            words: (0..count).map(|i| format!("word{i}")).collect(),
+            dice_per_word: Some(dice),
+        };
+        (0..count)
+            .rev()
+            .map(|i| format!("{}\tword{i}\n", wordlist.dice_index(i).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered() {
+        let result = parse_wordlist(make_numbered(2).as_bytes()).unwrap();
+        assert_eq!(result.dice_per_word, Some(2));
+        assert_eq!(result.words.len(), 36);
+        assert_eq!(result.words[0], "word0");
+        assert_eq!(result.words[35], "word35");
+        assert_eq!(result.dice_index(0).unwrap(), "11");
+        assert_eq!(result.dice_index(7).unwrap(), "22");
+        assert_eq!(result.dice_index(35).unwrap(), "66");
+        assert!(result.dice_index(36).is_none());
+        assert_eq!(result.word_for_roll("22"), Some("word7"));
+        assert!(result.word_for_roll("27").is_none());
+        assert!(result.word_for_roll("222").is_none());
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_eff_style() {
+        let numbered = make_numbered(1).replace('\t', "  ");
+        let result = parse_wordlist(numbered.as_bytes()).unwrap();
+        assert_eq!(result.dice_per_word, Some(1));
+        assert_eq!(result.word_for_roll("6"), Some("word5"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_incomplete() {
+        let err = parse_wordlist(b"1\tone\n2\ttwo\n").unwrap_err();
+        assert!(matches!(err, WordlistError::IncompleteDiceCoverage(6, 2)));
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_duplicate_index() {
+        let err = parse_wordlist(b"1\tone\n2\ttwo\n1\tuno\n").unwrap_err();
+        assert!(matches!(err, WordlistError::DuplicateDiceIndex(3, index) if index == "1"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_duplicate_word() {
+        let err = parse_wordlist(b"1\tone\n2\tone\n").unwrap_err();
+        assert!(matches!(err, WordlistError::DuplicateWord(2, word) if word == "one"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_bad_face() {
+        let err = parse_wordlist(b"1\tone\n7\tseven\n").unwrap_err();
+        assert!(matches!(err, WordlistError::InvalidDiceIndex(2, index) if index == "7"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_mixed_lengths() {
+        let err = parse_wordlist(b"1\tone\n12\ttwelve\n").unwrap_err();
+        assert!(matches!(err, WordlistError::InvalidDiceIndex(2, index) if index == "12"));
+    }
+
+    #[test]
+    fn test_parse_wordlist_numbered_missing_index() {
+        let err = parse_wordlist(b"1\tone\ntwo\n").unwrap_err();
+        assert!(matches!(err, WordlistError::InvalidDiceIndex(2, line) if line == "two"));
+    }
+
+    #[test]
+    fn test_plain_wordlist_has_no_dice_index() {
+        let result = parse_wordlist(b"correct\nhorse\n").unwrap();
+        assert!(result.dice_index(0).is_none());
+        assert!(result.word_for_roll("1").is_none());
+    }
+
+    #[test]
+    fn test_read_wordlist_missing_file() {
+        let err = read_wordlist("this/file/does/not/exist.txt").unwrap_err();
+        assert!(matches!(err, WordlistError::Io(..)));
+    }
+}
@@ -1,8 +1,12 @@
 //! Give enums superpowers.
 //!
+//! `#[cfg]` attributes on variants are copied onto everything generated for them,
+//! so members can be feature gated.
+//!
 //! ```
 //! #[derive(StrEnum, Copy, Clone, Debug)]
 //! pub enum RngType {
+//!     /// the system's native secure RNG
 //!     #[default]
 //!     OsRng,
 //!     Csprng,
@@ -27,6 +31,12 @@
 //!     fn into_iter() -> impl Iterator<Item = &'static (&'static str, Self)> {
 //!         Self::NAME_MEMBER_ARR.into_iter()
 //!     }
+//!     fn description(&self) -> &'static str {
+//!         match self {
+//!             Self::OsRng => "the system's native secure RNG",
+//!             Self::Csprng => "",
+//!         }
+//!     }
 //! }
 //! impl std::fmt::Display for RngType {
 //!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -41,7 +51,26 @@ use proc_macro::{self, TokenStream};
 
 use quote::quote;
 use stringcase::kebab_case;
-use syn::{DeriveInput, Fields, Ident, Meta, parse_macro_input};
+use syn::{Attribute, DeriveInput, Expr, ExprLit, Fields, Ident, Lit, Meta, parse_macro_input};
+
+/// Join the lines of an item's doc comment into a single line.
+fn doc_string(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("doc") => {
+                match &name_value.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(lit), ..
+                    }) => Some(lit.value().trim().to_owned()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
 
 /// Provides `StrEnum` derive macro
 ///
@@ -70,6 +99,10 @@ pub fn derive(input: TokenStream) -> TokenStream {
         .iter()
         .map(|variant| match variant.fields {
             Fields::Unit => {
+                #[expect(
+                    clippy::collapsible_if,
+                    reason = "each step of the attribute match reads clearer on its own line"
+                )]
                 for attr in &variant.attrs {
                     if let Meta::Path(path) = &attr.meta {
                         if let Some(ident) = path.get_ident() {
@@ -91,6 +124,24 @@ pub fn derive(input: TokenStream) -> TokenStream {
         })
         .collect::<Vec<Ident>>();
 
+    let descriptions = variants
+        .iter()
+        .map(|variant| doc_string(&variant.attrs))
+        .collect::<Vec<String>>();
+
+    // variants may be conditionally compiled, so everything generated per variant
+    // must carry the same `#[cfg]` attributes
+    let cfgs = variants
+        .iter()
+        .map(|variant| {
+            let attrs = variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("cfg"));
+            quote! { #(#attrs)* }
+        })
+        .collect::<Vec<_>>();
+
     let transformed_field_idents = field_idents
         .iter()
         .map(|v| kebab_case(&v.to_string()))
@@ -106,15 +157,20 @@ pub fn derive(input: TokenStream) -> TokenStream {
         }
         impl StrEnum for #enum_name {
             const NAME: &'static str = stringify!(#enum_name);
-            const NAME_MEMBER_ARR: &[(&'static str, Self)] = &[ #( (#transformed_field_idents, Self::#field_idents) ,)* ];
+            const NAME_MEMBER_ARR: &[(&'static str, Self)] = &[ #( #cfgs (#transformed_field_idents, Self::#field_idents) ,)* ];
             fn to_static_str(&self) -> &'static str {
                 match self {
-                    #(Self::#field_idents => #transformed_field_idents,)*
+                    #(#cfgs Self::#field_idents => #transformed_field_idents,)*
                 }
             }
             fn into_iter() -> impl Iterator<Item = &'static (&'static str, Self)> {
                 Self::NAME_MEMBER_ARR.into_iter()
             }
+            fn description(&self) -> &'static str {
+                match self {
+                    #(#cfgs Self::#field_idents => #descriptions,)*
+                }
+            }
         }
         impl std::fmt::Display for #enum_name {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {